@@ -2,17 +2,20 @@ pub mod pacman;
 mod procedures;
 
 use crate::action::procedures::{
-    add, build, build_all, build_info, build_logs, info, list, pkgbuild, remove, set_setting,
-    signing_key, subscribe_build_logs, webhook_secret,
+    add, build, build_all, build_info, build_log_section, build_logs, diff, info, list, pkgbuild,
+    queue, remove, review, set_setting, signing_key, stop, subscribe_build_logs, trust,
+    webhook_secret,
 };
-use crate::command::{Action, HostSubcommand, InfoCommand, ServerSubcommand};
+use crate::command::{Action, HostSubcommand, InfoCommand, SecretsSubcommand, ServerSubcommand};
 use crate::complete::generate_completions;
 use crate::config::Config;
 use crate::intro;
 use crate::log::Log;
 use clap_complete::Shell;
 use colored::Colorize;
-use procedures::server_info;
+use procedures::{
+    apply, audit, authorize_secret, export, list_secrets, purge, revoke_secret, server_info,
+};
 
 pub fn run(config: &Config, action: Action) {
     match action {
@@ -67,8 +70,21 @@ pub fn run(config: &Config, action: Action) {
         Action::Remove { name } => {
             remove(config, &name);
         }
-        Action::Build { names, clean, noresolve, gentle, install, listen, quiet, all, force } => {
-            if all {
+        Action::Build {
+            names,
+            clean,
+            noresolve,
+            gentle,
+            install,
+            listen,
+            quiet,
+            all,
+            force,
+            cancel,
+        } => {
+            if cancel {
+                stop(config, names);
+            } else if all {
                 build_all(config, force, !noresolve, clean);
             } else {
                 build(config, names, clean, !noresolve, install || listen, quiet, !gentle, listen);
@@ -77,6 +93,19 @@ pub fn run(config: &Config, action: Action) {
         Action::List => {
             list(config);
         }
+        Action::Queue => {
+            queue(config);
+        }
+        Action::Review { name, approve, reject } => {
+            let decision = if approve || reject { Some(approve) } else { None };
+            review(config, &name, decision);
+        }
+        Action::Export { output } => {
+            export(config, output);
+        }
+        Action::Apply { file, dry_run, prune } => {
+            apply(config, &file, dry_run, prune);
+        }
         Action::Info { name, what, all } => match what {
             None => {
                 info(config, &name, all);
@@ -84,24 +113,41 @@ pub fn run(config: &Config, action: Action) {
             Some(InfoCommand::Pkgbuild) => {
                 pkgbuild(config, &name);
             }
+            Some(InfoCommand::Diff { from, to }) => {
+                diff(config, &name, from, to);
+            }
             Some(InfoCommand::Build { id }) => {
                 build_info(config, &name, &id);
             }
-            Some(InfoCommand::Logs { id, subscribe, linger }) => {
-                if id.is_some() {
+            Some(InfoCommand::Logs { id, subscribe, linger, section }) => {
+                if let Some(section) = section {
+                    build_log_section(config, &name, &id, &section);
+                } else if id.is_some() {
                     build_logs(config, &name, &id);
                 } else {
                     subscribe_build_logs(config, &name, subscribe, linger);
                 }
             }
+            Some(InfoCommand::Trust) => {
+                trust(config, &name);
+            }
             Some(InfoCommand::Set { property }) => set_setting(config, &name, property),
         },
         Action::Server { manage } => match manage {
-            ServerSubcommand::Webhook { name, machine } => {
-                webhook_secret(config, &name, machine);
+            ServerSubcommand::Webhook { name, machine, rotate } => {
+                webhook_secret(config, &name, machine, rotate);
             }
             ServerSubcommand::Info => server_info(config),
             ServerSubcommand::Key { machine } => signing_key(config, machine),
+            ServerSubcommand::Purge => purge(config),
+            ServerSubcommand::Secrets { manage } => match manage {
+                SecretsSubcommand::List => list_secrets(config),
+                SecretsSubcommand::Add { secret, scopes, packages } => {
+                    authorize_secret(config, secret, scopes, packages)
+                }
+                SecretsSubcommand::Revoke { id } => revoke_secret(config, id),
+            },
+            ServerSubcommand::Audit { count } => audit(config, count),
         },
         Action::Completions => {
             let Some(shell) = Shell::from_env() else {
@@ -7,25 +7,32 @@ use crate::print::ago::{coarse, fine};
 use crate::print::table::{Column, table};
 use crate::print::{ago, bytes_str};
 use crate::web::data::{
-    BuildProgressFormatter, BuildReasonFormatter, BuildStateFormatter, describe_cron_timezone_hack,
+    BuildProgressFormatter, BuildReasonFormatter, BuildStateFormatter, LintSeverityFormatter,
+    describe_cron_timezone_hack,
 };
 use crate::web::requests::{
-    add_package, build_all_packages, build_package, get_build, get_build_logs, get_builds,
-    get_info, get_key, get_package, get_package_pkgbuild, get_packages, get_webhook_secret,
-    remove_package, set_package_setting, subscribe_events,
+    add_package, add_secret, apply_manifest, build_all_packages, build_package, get_audit,
+    get_build, get_build_log_section, get_build_log_sections, get_build_logs, get_builds, get_info,
+    get_key, get_manifest, get_package, get_package_diff, get_package_pkgbuild, get_package_review,
+    get_packages, get_queue, get_secrets, get_webhook_secret, purge_builds, remove_package,
+    remove_secret, review_package, rotate_webhook_secret, set_package_setting, stop_package_build,
+    subscribe_events, trust_package,
 };
 use chrono::{Duration, Local, Utc};
 use colored::{ColoredString, Colorize};
 use semver::Version;
-use serene_data::build::BuildState;
+use serene_data::build::{BuildState, LintFinding};
+use serene_data::manifest::{Manifest, ManifestApplyRequest, ManifestChange};
 use serene_data::package::{
-    BroadcastEvent, MakepkgFlag, PackageAddRequest, PackageAddSource, PackageBuildRequest,
-    PackageSettingsRequest,
+    BroadcastEvent, MakepkgFlag, NotifyRule, PackageAddRequest, PackageAddSource,
+    PackageBuildRequest, PackageReviewRequest, PackageSettingsRequest, ResourceLimits,
 };
+use serene_data::secret::{Scope, SecretAddRequest};
 use std::cell::RefCell;
 use std::env::consts::ARCH;
+use std::fs;
 use std::fs::File;
-use std::io::Read;
+use std::io::{IsTerminal, Read, Write, stdin, stdout};
 use std::str::FromStr;
 
 /// waits for a package to build and then installs it
@@ -64,11 +71,16 @@ fn wait_and_install(c: &Config, base: &str, quiet: bool, just_listen: bool) {
                 BuildState::Cancelled(_)
                 | BuildState::Success
                 | BuildState::Failure
-                | BuildState::Fatal(_, _) => {
+                | BuildState::Fatal(_, _)
+                | BuildState::Stopped(_)
+                | BuildState::Review => {
                     return true;
                 }
             },
-            BroadcastEvent::Ping => {}
+            BroadcastEvent::Remove => {
+                return true;
+            }
+            BroadcastEvent::Ping | BroadcastEvent::Add | BroadcastEvent::Settings => {}
         }
 
         false
@@ -132,6 +144,17 @@ fn wait_and_install(c: &Config, base: &str, quiet: bool, just_listen: bool) {
             log.fail(&format!("build was cancelled due to: {message}"));
             return;
         }
+        BuildState::Stopped(progress) => {
+            log.fail(&format!(
+                "build was stopped by a user whilst {}",
+                progress.printable_string()
+            ));
+            return;
+        }
+        BuildState::Review => {
+            log.fail(&format!("build files changed, review them with `serene review {base}`"));
+            return;
+        }
 
         // successful
         BuildState::Success => {
@@ -244,6 +267,18 @@ pub fn build_all(c: &Config, force: bool, resolve: bool, clean: bool) {
     }
 }
 
+/// stops the current builds of packages
+pub fn stop(c: &Config, packages: Vec<String>) {
+    for package in packages {
+        let log = Log::start(&format!("stopping build of package {}", package.italic()));
+
+        match stop_package_build(c, &package) {
+            Ok(()) => log.succeed("requested build to stop successfully"),
+            Err(e) => log.fail(&e.msg()),
+        }
+    }
+}
+
 /// builds packages right now
 pub fn build(
     c: &Config,
@@ -314,7 +349,13 @@ pub fn list(c: &Config) {
                             .map(|s| s.normal())
                             .unwrap_or_else(|| "never built".dimmed()),
                         if peek.devel { "X".dimmed() } else { "".dimmed() },
-                        if peek.enabled { "X".yellow() } else { "".dimmed() },
+                        if peek.maintainer_change.is_some() {
+                            "!".red()
+                        } else if peek.enabled {
+                            "X".yellow()
+                        } else {
+                            "".dimmed()
+                        },
                         peek.build
                             .as_ref()
                             .map(|p| p.state.colored_passive())
@@ -351,6 +392,82 @@ pub fn list(c: &Config) {
     }
 }
 
+/// show the current build queue of the server
+pub fn queue(c: &Config) {
+    let log = Log::start("querying build queue");
+
+    match get_queue(c) {
+        Ok(queue) => {
+            log.succeed("retrieved build queue successfully");
+
+            println!();
+            println!(
+                "{:<7} {}/{} in use, {} waiting",
+                "slots:",
+                queue.running,
+                queue.slots,
+                queue.waiting.len()
+            );
+
+            let columns = [
+                Column::new("name").ellipse(),
+                Column::new("status").force(),
+                Column::new("reason").force(),
+                Column::new("waiting for").ellipse(),
+            ];
+
+            let mut rows: Vec<[ColoredString; 4]> = vec![];
+            for session in &queue.sessions {
+                for package in &session.building {
+                    let status = match queue.waiting.iter().position(|p| p == package) {
+                        Some(i) => format!("queued #{}", i + 1).yellow(),
+                        None => "building".blue(),
+                    };
+
+                    rows.push([package.bold(), status, session.reason.colored(), "".normal()]);
+                }
+
+                for (package, dependencies) in &session.pending {
+                    rows.push([
+                        package.bold(),
+                        "pending".dimmed(),
+                        session.reason.colored(),
+                        dependencies.join(", ").normal(),
+                    ]);
+                }
+            }
+
+            println!();
+            if rows.is_empty() {
+                println!("{}", "no builds are running".dimmed())
+            } else {
+                table(columns, rows, "  ");
+            }
+
+            let columns = [Column::new("scheduled").force(), Column::new("packages").ellipse()];
+
+            let rows: Vec<[ColoredString; 2]> = queue
+                .scheduled
+                .iter()
+                .map(|target| {
+                    [
+                        target.time.with_timezone(&Local).format("%x %X").to_string().normal(),
+                        target.packages.join(", ").normal(),
+                    ]
+                })
+                .collect();
+
+            println!();
+            if rows.is_empty() {
+                println!("{}\n", "no builds are scheduled".dimmed())
+            } else {
+                table(columns, rows, "  ");
+            }
+        }
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
 /// get information about package and its builds
 pub fn info(c: &Config, package: &str, all: bool) {
     check_version_mismatch(c);
@@ -396,6 +513,13 @@ pub fn info(c: &Config, package: &str, all: bool) {
         if info.members.is_empty() { "none yet".dimmed() } else { info.members.join(" ").normal() }
     );
     println!("{:<9} {}", "added:", info.added.with_timezone(&Local).format("%x %X"));
+    println!(
+        "{:<9} {}",
+        "webhook:",
+        info.webhook_fired
+            .map(|t| format!("last fired {}", t.with_timezone(&Local).format("%x %X")).normal())
+            .unwrap_or_else(|| "never fired".italic().dimmed())
+    );
     println!(
         "{:<9} {} {}",
         "source:",
@@ -424,6 +548,11 @@ pub fn info(c: &Config, package: &str, all: bool) {
     if info.private {
         tags.push("private".bright_black())
     }
+    if info.review_pending {
+        tags.push("awaiting-review".bright_cyan())
+    } else if info.review {
+        tags.push("review".cyan())
+    }
 
     println!(
         "{:<9} {}",
@@ -431,6 +560,26 @@ pub fn info(c: &Config, package: &str, all: bool) {
         tags.iter().map(|s| s.to_string()).intersperse(" ".to_string()).collect::<String>()
     );
 
+    if let Some(change) = &info.maintainer_change {
+        println!(
+            "{:<9} {} {}",
+            "trust:",
+            format!(
+                "{} on {}",
+                change.describe(),
+                change.detected.with_timezone(&Local).format("%x %X")
+            )
+            .red(),
+            format!("(acknowledge with `serene info {} trust`)", info.base).dimmed()
+        );
+    } else if info.maintainer_tracked == Some(false) {
+        println!(
+            "{:<9} {}",
+            "trust:",
+            "maintainer could not be looked up on the aur, changes are not detected".yellow()
+        );
+    }
+
     println!(
         "{:<9} {}{}",
         "schedule:",
@@ -439,6 +588,38 @@ pub fn info(c: &Config, package: &str, all: bool) {
         if info.schedule_changed { " *".red() } else { "".normal() }
     );
 
+    println!(
+        "{:<9} {}{}",
+        "timeout:",
+        info.timeout
+            .map(|t| format!("{t} minutes").normal())
+            .unwrap_or_else(|| "none".italic().dimmed()),
+        if info.timeout_changed { " *".red() } else { "".normal() }
+    );
+
+    let limits = [
+        info.limits.memory.map(|m| bytes_str(m as usize * 1024 * 1024)),
+        info.limits.cpus.map(|c| format!("{c} cpus")),
+        info.limits.pids.map(|p| format!("{p} processes")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    println!(
+        "{:<9} {}{}",
+        "limits:",
+        if limits.is_empty() { "none".italic().dimmed() } else { limits.join(", ").normal() },
+        if info.limits_changed { " *".red() } else { "".normal() }
+    );
+
+    println!(
+        "{:<9} {}{}",
+        "notify:",
+        info.notify,
+        if info.notify_changed { " *".red() } else { "".normal() }
+    );
+
     println!(
         "{:<9} {}",
         "flags:",
@@ -491,6 +672,33 @@ pub fn info(c: &Config, package: &str, all: bool) {
         .collect();
 
     table(columns, rows, "  ");
+
+    if let Some(failure) = builds.first().and_then(|b| b.failure.as_ref()) {
+        println!();
+        println!("{} {}", "latest build failed:".red(), failure.category.to_string().bold());
+        println!("{}", failure.hint.italic());
+    }
+
+    if let Some(build) = builds.first()
+        && !build.findings.is_empty()
+    {
+        println!();
+        println!("{}", "findings of the latest build:".yellow());
+        print_findings(&build.findings);
+    }
+}
+
+/// prints the lint findings of a build
+fn print_findings(findings: &[LintFinding]) {
+    for finding in findings {
+        println!(
+            "  {} {} in {}: {}",
+            finding.severity.colored(),
+            finding.rule.to_string().bold(),
+            finding.file,
+            finding.message.italic()
+        );
+    }
 }
 
 /// get build information
@@ -522,18 +730,29 @@ pub fn build_info(c: &Config, package: &str, build: &Option<String>) {
             );
 
             let additive = match &b.state {
-                BuildState::Running(state) | BuildState::Fatal(_, state) => {
+                BuildState::Running(state)
+                | BuildState::Fatal(_, state)
+                | BuildState::Stopped(state) => {
                     format!("on {}", state.printable_string())
                 }
                 _ => "".to_string(),
             };
 
-            println!("{:<8} {}", "reason:", b.reason.colored());
+            if let Some(commit) = &b.commit {
+                println!("{:<8} {} for {}", "reason:", b.reason.colored(), commit.italic());
+            } else {
+                println!("{:<8} {}", "reason:", b.reason.colored());
+            }
             println!("\n{:<8} {} {}", "status:", b.state.colored_substantive(), additive);
 
             match &b.state {
                 BuildState::Failure => {
-                    println!("{:<8} {}", "message:", "see logs for error messages".italic())
+                    if let Some(failure) = &b.failure {
+                        println!("{:<8} {}", "failure:", failure.category.to_string().bold());
+                        println!("{:<8} {}", "hint:", failure.hint.italic());
+                    } else {
+                        println!("{:<8} {}", "message:", "see logs for error messages".italic())
+                    }
                 }
                 BuildState::Fatal(msg, _) => {
                     println!("{:<8} {}", "message:", msg)
@@ -555,6 +774,11 @@ pub fn build_info(c: &Config, package: &str, build: &Option<String>) {
                 if let Some(mem_peak) = b.mem_peak {
                     println!("  {:<12} {}", "peak memory:", bytes_str(mem_peak))
                 }
+                if let Some(oom_kills) = b.oom_kills
+                    && oom_kills > 0
+                {
+                    println!("  {:<12} {}", "oom kills:", oom_kills.to_string().red())
+                }
                 if b.cpu_user.is_some() || b.cpu_system.is_some() {
                     let mut line = String::new();
                     let both = b.cpu_user.is_some() && b.cpu_system.is_some();
@@ -600,6 +824,11 @@ pub fn build_info(c: &Config, package: &str, build: &Option<String>) {
                     println!("  {:<12} {} read, {} written", "io:", total_read, total_write)
                 }
             }
+
+            if !b.findings.is_empty() {
+                println!("\nlint findings:");
+                print_findings(&b.findings);
+            }
         }
         Err(e) => log.fail(&e.msg()),
     }
@@ -620,13 +849,67 @@ pub fn build_logs(c: &Config, package: &str, build: &Option<String>) {
     }
 }
 
+/// get a single section of the logs for a build
+pub fn build_log_section(c: &Config, package: &str, build: &Option<String>, section: &str) {
+    let mut log = Log::start("fetching sections of the build logs");
+
+    let id = build.clone().unwrap_or("latest".to_string());
+
+    let sections = match get_build_log_sections(c, package, &id) {
+        Ok(sections) => sections,
+        Err(e) => {
+            log.fail(&e.msg());
+            return;
+        }
+    };
+
+    let Some(info) = sections.iter().find(|s| s.name == section) else {
+        let names = sections.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+        log.fail(&format!(
+            "build logs have no section '{section}', available are: {}",
+            names.join(", ")
+        ));
+        return;
+    };
+
+    log.next(&format!("fetching logs of section {section}"));
+
+    match get_build_log_section(c, package, &id, section) {
+        Ok(logs) => {
+            log.succeed("fetched build logs successfully");
+
+            let duration = info
+                .duration
+                .map(|d| fine(Duration::seconds(d), false, false))
+                .unwrap_or_else(|| "unknown duration".to_string());
+
+            println!(
+                "{} {}",
+                info.message.italic(),
+                format!("({duration}, {} lines)", info.lines).dimmed()
+            );
+            println!("{logs}")
+        }
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
 /// print the personalized webhook secret for a package
-pub fn webhook_secret(c: &Config, package: &str, machine: bool) {
-    let log = Log::start("requesting webhook secret");
+pub fn webhook_secret(c: &Config, package: &str, machine: bool, rotate: bool) {
+    let (log, result) = if rotate {
+        (Log::start("rotating webhook secrets"), rotate_webhook_secret(c, package))
+    } else {
+        (Log::start("requesting webhook secret"), get_webhook_secret(c, package))
+    };
 
-    match get_webhook_secret(c, package) {
+    match result {
         Ok(secret) => {
-            log.succeed("received webhook secret successfully");
+            if rotate {
+                log.succeed("rotated webhook secrets successfully, old secrets no longer work");
+            } else {
+                log.succeed("received webhook secret successfully");
+            }
+
             if machine {
                 println!("{secret}")
             } else {
@@ -645,6 +928,159 @@ pub fn webhook_secret(c: &Config, package: &str, machine: bool) {
     }
 }
 
+/// purge old builds on the server
+pub fn purge(c: &Config) {
+    let log = Log::start("purging old builds");
+
+    match purge_builds(c) {
+        Ok(info) => log.succeed(&format!(
+            "purged {} builds, freeing {} of logs",
+            info.builds,
+            bytes_str(info.freed as usize)
+        )),
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
+/// list the authorized secrets of the server in a table
+pub fn list_secrets(c: &Config) {
+    let log = Log::start("querying authorized secrets");
+
+    match get_secrets(c) {
+        Ok(secrets) => {
+            log.succeed("retrieved authorized secrets successfully");
+
+            let columns = [
+                Column::new("id").force(),
+                Column::new("comment").ellipse(),
+                Column::new("scopes").force(),
+                Column::new("packages").ellipse(),
+                Column::new("last used").force(),
+            ];
+
+            let rows: Vec<[ColoredString; 5]> = secrets
+                .iter()
+                .map(|secret| {
+                    [
+                        secret.id.to_string().dimmed(),
+                        secret.comment.bold(),
+                        secret
+                            .scopes
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect::<Vec<_>>()
+                            .join(",")
+                            .normal(),
+                        if secret.packages.is_empty() {
+                            "all".dimmed()
+                        } else {
+                            secret.packages.join(",").normal()
+                        },
+                        secret
+                            .used
+                            .and_then(|used| coarse(Utc::now() - used, false, false, false))
+                            .map(|ago| format!("{ago} ago").normal())
+                            .unwrap_or_else(|| "never".dimmed()),
+                    ]
+                })
+                .collect();
+
+            println!();
+            if rows.is_empty() {
+                println!("{}\n", "no secrets authorized".dimmed())
+            } else {
+                table(columns, rows, "  ");
+            }
+        }
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
+/// authorize a secret on the server from the line printed by the other host
+pub fn authorize_secret(c: &Config, line: Vec<String>, scopes: Vec<String>, packages: Vec<String>) {
+    let log = Log::start("authorizing secret");
+
+    let Some((hash, comment)) = line.split_first() else {
+        log.fail("no secret provided");
+        return;
+    };
+
+    let scopes = match scopes
+        .iter()
+        .map(|s| Scope::from_str(s).map_err(|_| s))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(scopes) => scopes,
+        Err(s) => {
+            log.fail(&format!("scope {s} not supported, use read, build, manage or admin"));
+            return;
+        }
+    };
+
+    let request = SecretAddRequest {
+        hash: hash.clone(),
+        comment: comment.join(" "),
+        // secrets have full access by default
+        scopes: if scopes.is_empty() { vec![Scope::Admin] } else { scopes },
+        packages,
+    };
+
+    match add_secret(c, request) {
+        Ok(secret) => log.succeed(&format!("authorized secret with id {}", secret.id)),
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
+/// revoke an authorized secret on the server
+pub fn revoke_secret(c: &Config, id: i64) {
+    let log = Log::start(&format!("revoking secret with id {id}"));
+
+    match remove_secret(c, id) {
+        Ok(()) => log.succeed("revoked secret successfully"),
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
+/// show the latest entries of the audit log in a table
+pub fn audit(c: &Config, count: Option<u32>) {
+    let log = Log::start("querying audit log");
+
+    match get_audit(c, count) {
+        Ok(entries) => {
+            log.succeed("retrieved audit log successfully");
+
+            let columns = [
+                Column::new("date").force(),
+                Column::new("secret"),
+                Column::new("endpoint"),
+                Column::new("body").ellipse(),
+            ];
+
+            // show the oldest entry first, like logs
+            let rows: Vec<[ColoredString; 4]> = entries
+                .iter()
+                .rev()
+                .map(|entry| {
+                    [
+                        entry.time.with_timezone(&Local).format("%x %X").to_string().dimmed(),
+                        entry.secret.bold(),
+                        entry.endpoint.normal(),
+                        entry.body.as_ref().map(|b| b.normal()).unwrap_or_else(|| "".normal()),
+                    ]
+                })
+                .collect();
+
+            println!();
+            if rows.is_empty() {
+                println!("{}\n", "no actions recorded yet".dimmed())
+            } else {
+                table(columns, rows, "  ");
+            }
+        }
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
 /// print the signing key from the server
 pub fn signing_key(c: &Config, machine: bool) {
     let log = Log::start("getting server public key");
@@ -720,11 +1156,15 @@ pub fn subscribe_build_logs(c: &Config, package: &str, explicit: bool, linger: b
 
         // ignore unknown events
         match event {
-            BroadcastEvent::Ping => {}
+            BroadcastEvent::Ping | BroadcastEvent::Add | BroadcastEvent::Settings => {}
             BroadcastEvent::Change(state) => {
                 println!("{} {}", "### package build is".italic().dimmed(), state.colored_passive())
             }
             BroadcastEvent::Log(data) => print!("{data}"),
+            BroadcastEvent::Remove => {
+                println!("{}", "### package was removed".italic().dimmed());
+                return true;
+            }
         }
 
         false // stay attached
@@ -831,6 +1271,57 @@ pub fn set_setting(c: &Config, package: &str, setting: SettingsSubcommand) {
             ));
             PackageSettingsRequest::SrcinfoOverride(force)
         }
+        SettingsSubcommand::Timeout { minutes } => {
+            match minutes {
+                None => log.next(&format!("reverting to default timeout for package {package}")),
+                Some(0) => log.next(&format!("disabling timeout for package {package}")),
+                Some(m) => {
+                    log.next(&format!("setting timeout of {m} minutes for package {package}"))
+                }
+            }
+
+            PackageSettingsRequest::Timeout(minutes)
+        }
+        SettingsSubcommand::Limits { memory, cpus, pids } => {
+            let limits = ResourceLimits { memory, cpus, pids };
+
+            if limits.is_empty() {
+                log.next(&format!("reverting to default resource limits for package {package}"));
+            } else {
+                log.next(&format!("setting custom resource limits for package {package}"));
+            }
+
+            PackageSettingsRequest::Limits(limits)
+        }
+        SettingsSubcommand::Notify { rule } => {
+            let rule = match rule.map(|r| NotifyRule::from_str(&r).map_err(|_| r)).transpose() {
+                Ok(rule) => rule,
+                Err(r) => {
+                    log.fail(&format!(
+                        "notification rule {r} not supported, use never, failure, recovery or always"
+                    ));
+                    return;
+                }
+            };
+
+            match rule {
+                None => {
+                    log.next(&format!("reverting to default notifications for package {package}"))
+                }
+                Some(r) => {
+                    log.next(&format!("setting notification rule {r} for package {package}"))
+                }
+            }
+
+            PackageSettingsRequest::Notify(rule)
+        }
+        SettingsSubcommand::Review { enabled } => {
+            log.next(&format!(
+                "{} reviews of changes for package {package}",
+                if enabled { "requiring" } else { "no longer requiring" }
+            ));
+            PackageSettingsRequest::Review(enabled)
+        }
     };
 
     match set_package_setting(c, package, request) {
@@ -852,6 +1343,119 @@ pub fn pkgbuild(c: &Config, package: &str) {
     }
 }
 
+/// prints a unified diff with colors
+fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            println!("{}", line.bold());
+        } else if line.starts_with('+') {
+            println!("{}", line.green());
+        } else if line.starts_with('-') {
+            println!("{}", line.red());
+        } else if line.starts_with("@@") {
+            println!("{}", line.cyan());
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+/// show changes of the build files of a package
+pub fn diff(c: &Config, package: &str, from: Option<String>, to: Option<String>) {
+    let log = Log::start("fetching changes of the build files");
+
+    match get_package_diff(c, package, from, to) {
+        Ok(diff) if diff.is_empty() => log.succeed("build files did not change"),
+        Ok(diff) => {
+            log.succeed("successfully fetched changes of the build files");
+            print_diff(&diff);
+        }
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
+/// acknowledges the changed maintainer of a package
+pub fn trust(c: &Config, package: &str) {
+    let log = Log::start("acknowledging maintainer change");
+
+    match trust_package(c, package) {
+        Ok(()) => {
+            log.succeed(&format!("acknowledged maintainer change of {package}"));
+            Log::warning(&format!(
+                "{package} is still disabled, enable it with `serene info {package} set enable true`"
+            ));
+        }
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
+/// asks whether changes should be approved, none if no decision was made
+fn prompt_review() -> anyhow::Result<Option<bool>> {
+    print!("approve these changes? [a/r/N] ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    let answer = answer.trim().to_lowercase();
+
+    Ok(if answer.starts_with('a') {
+        Some(true)
+    } else if answer.starts_with('r') {
+        Some(false)
+    } else {
+        None
+    })
+}
+
+/// review changes to the build files of a package
+pub fn review(c: &Config, package: &str, decision: Option<bool>) {
+    let log = Log::start("fetching changes awaiting review");
+
+    let review = match get_package_review(c, package) {
+        Ok(review) => review,
+        Err(e) => {
+            log.fail(&e.msg());
+            return;
+        }
+    };
+
+    log.succeed(&format!(
+        "build files changed on {}",
+        review.created.with_timezone(&Local).format("%x %X")
+    ));
+
+    println!();
+    print_diff(&review.diff);
+    println!();
+
+    let approve = match decision {
+        Some(approve) => approve,
+        None if !stdin().is_terminal() => {
+            Log::warning("changes are left for review, use --approve or --reject to decide");
+            return;
+        }
+        None => match prompt_review() {
+            Ok(Some(approve)) => approve,
+            Ok(None) => {
+                Log::warning("changes are left for review, the package is not built until then");
+                return;
+            }
+            Err(e) => {
+                Log::failure(&format!("failed to read decision: {e:#}"));
+                return;
+            }
+        },
+    };
+
+    let log = Log::start(if approve { "approving changes" } else { "rejecting changes" });
+
+    match review_package(c, package, PackageReviewRequest { approve }) {
+        Ok(()) if approve => log.succeed(&format!("approved changes, building {package} now")),
+        Ok(()) => log.succeed("rejected changes, they won't be built"),
+        Err(e) => log.fail(&e.msg()),
+    }
+}
+
 /// checks for the server version and prints a warning if a mismatch is found
 pub fn check_version_mismatch(c: &Config) {
     if let Ok(info) = get_info(c) {
@@ -930,7 +1534,9 @@ pub fn server_info(c: &Config) {
                 BuildState::Running(_) => working += 1,
                 BuildState::Success => passing += 1,
                 BuildState::Failure => failing += 1,
-                BuildState::Cancelled(_) => cancelled += 1,
+                BuildState::Cancelled(_) | BuildState::Stopped(_) | BuildState::Review => {
+                    cancelled += 1
+                }
                 BuildState::Fatal(_, _) => fatal += 1,
             }
         }
@@ -1014,3 +1620,103 @@ pub fn server_info(c: &Config) {
         if ARCH == info.architecture { "compatible".normal() } else { "incompatible".red() }
     )
 }
+
+/// print the manifest of the server, or write it to a file
+pub fn export(c: &Config, output: Option<String>) {
+    let log = Log::start("exporting manifest");
+
+    let manifest = match get_manifest(c) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            log.fail(&e.msg());
+            return;
+        }
+    };
+
+    let yaml = match serde_yaml::to_string(&manifest) {
+        Ok(yaml) => yaml,
+        Err(e) => {
+            log.fail(&format!("failed to serialize manifest: {e:#}"));
+            return;
+        }
+    };
+
+    if let Some(output) = output {
+        if let Err(e) = fs::write(&output, yaml) {
+            log.fail(&format!("failed to write manifest: {e:#}"));
+            return;
+        }
+
+        log.succeed(&format!("exported {} packages to {output}", manifest.packages.len()));
+    } else {
+        log.succeed(&format!("exported {} packages", manifest.packages.len()));
+        print!("{yaml}");
+    }
+}
+
+/// apply a manifest file to the server and print the changes
+pub fn apply(c: &Config, file: &str, dry_run: bool, prune: bool) {
+    let mut log = Log::start("loading manifest");
+
+    let manifest = match fs::read_to_string(file) {
+        Ok(string) => string,
+        Err(e) => {
+            log.fail(&format!("failed to read manifest: {e:#}"));
+            return;
+        }
+    };
+
+    let manifest: Manifest = match serde_yaml::from_str(&manifest) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            log.fail(&format!("failed to parse manifest: {e:#}"));
+            return;
+        }
+    };
+
+    log.next(if dry_run { "calculating changes" } else { "applying manifest" });
+
+    match apply_manifest(c, ManifestApplyRequest { manifest, dry_run, prune }) {
+        Ok(changes) => {
+            if changes.is_empty() {
+                log.succeed("server already matches the manifest");
+                return;
+            }
+
+            if dry_run {
+                log.succeed(&format!("applying the manifest would make {} changes", changes.len()));
+            } else {
+                log.succeed(&format!("applied {} changes successfully", changes.len()));
+            }
+
+            println!();
+            for change in changes {
+                match change {
+                    ManifestChange::Add { base } => {
+                        println!("{} {}", "+".green().bold(), base.bold())
+                    }
+                    ManifestChange::Replace { base } => {
+                        println!(
+                            "{} {} {}",
+                            "~".yellow().bold(),
+                            base.bold(),
+                            "new source".italic()
+                        )
+                    }
+                    ManifestChange::Set { base, setting, old, new } => println!(
+                        "{} {} {setting}: {} -> {}",
+                        "~".yellow().bold(),
+                        base.bold(),
+                        old.dimmed(),
+                        new
+                    ),
+                    ManifestChange::Remove { base } => {
+                        println!("{} {}", "-".red().bold(), base.bold())
+                    }
+                }
+            }
+            println!();
+        }
+        Err(e) => log.fail(&e.msg()),
+    }
+}
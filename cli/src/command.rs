@@ -106,6 +106,10 @@ pub enum Action {
         /// force the build of all packages, including up-to-date
         #[clap(short, long, requires = "all", help_heading = "All")]
         force: bool,
+
+        /// stop the current builds of the packages instead
+        #[clap(long, conflicts_with_all = ["all", "logs", "clean", "noresolve", "gentle"])]
+        cancel: bool,
     },
 
     /// get and set info about a package
@@ -122,6 +126,44 @@ pub enum Action {
         what: Option<InfoCommand>,
     },
 
+    /// show the current build queue of the server
+    Queue,
+
+    /// review changes to the build files of a package before they are built
+    Review {
+        /// base name of the package
+        name: String,
+
+        /// approve the changes and build the package
+        #[clap(short, long, conflicts_with = "reject")]
+        approve: bool,
+
+        /// reject the changes, they won't be built
+        #[clap(short, long)]
+        reject: bool,
+    },
+
+    /// print a manifest of all packages and their settings
+    Export {
+        /// write the manifest to a file instead
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// add, change and remove packages to match a manifest
+    Apply {
+        /// manifest file, as created by `serene export`
+        file: String,
+
+        /// only show the changes, without applying them
+        #[clap(short, long)]
+        dry_run: bool,
+
+        /// remove packages which are not part of the manifest
+        #[clap(short, long)]
+        prune: bool,
+    },
+
     /// manage things about the server
     Server {
         #[clap(subcommand)]
@@ -158,11 +200,30 @@ pub enum InfoCommand {
         /// stay attached indefinitely
         #[clap(short, long)]
         linger: bool,
+
+        /// only print a section of the logs, e.g. build
+        #[clap(long)]
+        section: Option<String>,
     },
 
     /// get the pkgbuild used to build the current package
     Pkgbuild,
 
+    /// show changes of the build files, from the built ones to the next build
+    /// by default
+    Diff {
+        /// id of the build to compare from
+        #[clap(short, long)]
+        from: Option<String>,
+
+        /// id of the build to compare to
+        #[clap(short, long)]
+        to: Option<String>,
+    },
+
+    /// acknowledge a changed maintainer of the aur package
+    Trust,
+
     /// set property of the package
     Set {
         /// property to set
@@ -184,6 +245,11 @@ pub enum ServerSubcommand {
         /// print the secret in a machine-readable way
         #[clap(short, long)]
         machine: bool,
+
+        /// rotate the webhook secrets of the package, invalidating the
+        /// existing ones of all users
+        #[clap(short, long)]
+        rotate: bool,
     },
 
     /// get the public key of the server
@@ -192,6 +258,50 @@ pub enum ServerSubcommand {
         #[clap(short, long)]
         machine: bool,
     },
+
+    /// purge old builds and their logs according to the retention rules
+    Purge,
+
+    /// manage the secrets which are authorized on the server
+    Secrets {
+        #[clap(subcommand)]
+        manage: SecretsSubcommand,
+    },
+
+    /// show who performed which actions on the server
+    Audit {
+        /// amount of entries to show, newest first
+        #[clap(short, long)]
+        count: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SecretsSubcommand {
+    /// list all authorized secrets
+    List,
+
+    /// authorize a new secret
+    Add {
+        /// line printed by `serene host secret` on the machine to trust
+        #[clap(required = true)]
+        secret: Vec<String>,
+
+        /// scopes to grant, comma separated, full access if empty
+        #[clap(short, long, value_delimiter = ',')]
+        scopes: Vec<String>,
+
+        /// package bases the secret may access, comma separated, where `*`
+        /// matches any characters
+        #[clap(short, long, value_delimiter = ',')]
+        packages: Vec<String>,
+    },
+
+    /// revoke an authorized secret
+    Revoke {
+        /// id of the secret, as shown by the list
+        id: i64,
+    },
 }
 
 #[derive(Subcommand)]
@@ -270,4 +380,38 @@ pub enum SettingsSubcommand {
         #[arg(action = ArgAction::Set)]
         force: bool,
     },
+
+    /// set custom build timeout
+    Timeout {
+        /// minutes after which the build is killed, 0 to disable, default if
+        /// empty
+        minutes: Option<u32>,
+    },
+
+    /// set custom resource limits for the build container, omitted limits
+    /// revert to the default
+    Limits {
+        /// memory limit in mebibytes, 0 to disable
+        #[clap(long)]
+        memory: Option<u64>,
+        /// amount of cpus the build can use, 0 to disable
+        #[clap(long)]
+        cpus: Option<f32>,
+        /// maximal amount of processes, 0 to disable
+        #[clap(long)]
+        pids: Option<u64>,
+    },
+
+    /// set on which build results notifications are sent
+    Notify {
+        /// one of never, failure, recovery or always, default if empty
+        rule: Option<String>,
+    },
+
+    /// require changes to the build files to be reviewed before building
+    Review {
+        /// hold builds until changes are approved
+        #[arg(action = ArgAction::Set)]
+        enabled: bool,
+    },
 }
@@ -3,10 +3,11 @@ use clap_complete::Shell;
 use std::io::BufWriter;
 
 #[rustfmt::skip]
-const PACKAGE_COMPLETION_COMMANDS: [(&str, bool); 4] = [
+const PACKAGE_COMPLETION_COMMANDS: [(&str, bool); 5] = [
     ("info",            false), 
     ("build",           true), 
     ("remove",          false), 
+    ("review",          false), 
     ("manage webhook",  false),
 ];
 
@@ -3,7 +3,7 @@ use chrono::{Local, Offset};
 use colored::{ColoredString, Colorize};
 use cron_descriptor::cronparser::cron_expression_descriptor::get_description_cron_options;
 use cron_descriptor::cronparser::Options;
-use serene_data::build::{BuildProgress, BuildReason, BuildState};
+use serene_data::build::{BuildProgress, BuildReason, BuildState, LintSeverity};
 use std::str::FromStr;
 
 pub trait BuildStateFormatter {
@@ -20,6 +20,8 @@ impl BuildStateFormatter for BuildState {
             BuildState::Success => "passing".green(),
             BuildState::Failure => "failing".red(),
             BuildState::Fatal(_, _) => "fatal".bright_red(),
+            BuildState::Stopped(_) => "stopped".bright_magenta(),
+            BuildState::Review => "review".bright_cyan(),
         }
     }
 
@@ -31,6 +33,8 @@ impl BuildStateFormatter for BuildState {
             BuildState::Success => "success".green(),
             BuildState::Failure => "failure".red(),
             BuildState::Fatal(_, _) => "fatal".bright_red(),
+            BuildState::Stopped(_) => "stopped".bright_magenta(),
+            BuildState::Review => "review".bright_cyan(),
         }
     }
 }
@@ -46,11 +50,26 @@ impl BuildReasonFormatter for BuildReason {
             BuildReason::Manual => "manual".bright_blue(),
             BuildReason::Schedule => "schedule".dimmed(),
             BuildReason::Initial => "initial".bright_blue(),
+            BuildReason::Retry => "retry".yellow(),
             BuildReason::Unknown => "unknown".dimmed(),
         }
     }
 }
 
+pub trait LintSeverityFormatter {
+    fn colored(&self) -> ColoredString;
+}
+
+impl LintSeverityFormatter for LintSeverity {
+    fn colored(&self) -> ColoredString {
+        match self {
+            LintSeverity::Off => "off".dimmed(),
+            LintSeverity::Warn => "warn".yellow(),
+            LintSeverity::Block => "block".red(),
+        }
+    }
+}
+
 pub trait BuildProgressFormatter {
     fn printable_string(&self) -> String;
 }
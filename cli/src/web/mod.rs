@@ -113,6 +113,17 @@ pub fn get_raw(config: &Config, path: &str) -> Result<String> {
     process_errors(result)?.text().map_err(|e| Error::Client { error: e })
 }
 
+/// gets a plain text response, for endpoints which only send it when asked to
+pub fn get_text(config: &Config, path: &str) -> Result<String> {
+    let result = Client::new()
+        .get(get_url(config, path))
+        .header("Authorization", &config.secret)
+        .header("Accept", "text/plain")
+        .send();
+
+    process_errors(result)?.text().map_err(|e| Error::Client { error: e })
+}
+
 pub fn eventsource<F>(config: &Config, path: &str, mut cb: F) -> Result<()>
 where
     F: FnMut(Event) -> bool,
@@ -1,12 +1,14 @@
 use crate::config::Config;
-use crate::web::{delete_empty, eventsource, get, get_raw, post, post_simple, Result};
+use crate::web::{delete_empty, eventsource, get, get_raw, get_text, post, post_simple, Result};
 use reqwest_eventsource::Event;
-use serene_data::build::BuildInfo;
+use serene_data::build::{BuildInfo, BuildLogSection, BuildQueue};
+use serene_data::manifest::{Manifest, ManifestApplyRequest, ManifestChange};
 use serene_data::package::{
     BroadcastEvent, PackageAddRequest, PackageBuildRequest, PackageInfo, PackagePeek,
-    PackageSettingsRequest,
+    PackageReview, PackageReviewRequest, PackageSettingsRequest,
 };
-use serene_data::SereneInfo;
+use serene_data::secret::{SecretAddRequest, SecretInfo};
+use serene_data::{AuditInfo, PurgeInfo, SereneInfo};
 
 pub fn get_info(c: &Config) -> Result<SereneInfo> {
     get::<SereneInfo>(c, "")
@@ -27,6 +29,11 @@ pub fn build_package(c: &Config, request: PackageBuildRequest) -> Result<()> {
     post_simple(c, "build", request)
 }
 
+/// stop the current build of a package
+pub fn stop_package_build(c: &Config, package: &str) -> Result<()> {
+    delete_empty(c, &format!("package/{package}/build"))
+}
+
 /// build all packages immediately
 pub fn build_all_packages(c: &Config, request: PackageBuildRequest) -> Result<()> {
     post_simple(c, "build/all", request)
@@ -55,7 +62,17 @@ pub fn get_builds(c: &Config, package: &str, amount: Option<u32>) -> Result<Vec<
 
 /// gets the logs of a build
 pub fn get_build_logs(c: &Config, package: &str, id: &str) -> Result<String> {
-    get::<String>(c, &format!("package/{package}/build/{id}/logs"))
+    get_text(c, &format!("package/{package}/build/{id}/logs"))
+}
+
+/// gets the sections of the logs of a build
+pub fn get_build_log_sections(c: &Config, package: &str, id: &str) -> Result<Vec<BuildLogSection>> {
+    get::<Vec<BuildLogSection>>(c, &format!("package/{package}/build/{id}/sections"))
+}
+
+/// gets the logs of a single section of a build
+pub fn get_build_log_section(c: &Config, package: &str, id: &str, section: &str) -> Result<String> {
+    get_raw(c, &format!("package/{package}/build/{id}/sections/{section}"))
 }
 
 /// get the secret for the webhook of a given package
@@ -63,11 +80,53 @@ pub fn get_webhook_secret(c: &Config, package: &str) -> Result<String> {
     get::<String>(c, &format!("webhook/package/{package}/secret"))
 }
 
+/// rotate the webhook secrets of a given package and get the new one
+pub fn rotate_webhook_secret(c: &Config, package: &str) -> Result<String> {
+    post::<(), String>(c, &format!("webhook/package/{package}/rotate"), ())
+}
+
 /// get the key of the server
 pub fn get_key(c: &Config) -> Result<String> {
     get_raw(c, "key")
 }
 
+/// purge old builds on the server
+pub fn purge_builds(c: &Config) -> Result<PurgeInfo> {
+    post::<(), PurgeInfo>(c, "purge", ())
+}
+
+/// get all authorized secrets of the server
+pub fn get_secrets(c: &Config) -> Result<Vec<SecretInfo>> {
+    get::<Vec<SecretInfo>>(c, "secrets")
+}
+
+/// authorize a new secret on the server
+pub fn add_secret(c: &Config, request: SecretAddRequest) -> Result<SecretInfo> {
+    post::<SecretAddRequest, SecretInfo>(c, "secrets", request)
+}
+
+/// revoke an authorized secret on the server
+pub fn remove_secret(c: &Config, id: i64) -> Result<()> {
+    delete_empty(c, &format!("secrets/{id}"))
+}
+
+/// get the latest entries of the audit log of the server
+pub fn get_audit(c: &Config, amount: Option<u32>) -> Result<Vec<AuditInfo>> {
+    let query = amount.map(|u| format!("?count={u}")).unwrap_or_default();
+
+    get::<Vec<AuditInfo>>(c, &format!("audit{query}"))
+}
+
+/// get the manifest of all packages on the server
+pub fn get_manifest(c: &Config) -> Result<Manifest> {
+    get::<Manifest>(c, "manifest")
+}
+
+/// apply a manifest to the server
+pub fn apply_manifest(c: &Config, request: ManifestApplyRequest) -> Result<Vec<ManifestChange>> {
+    post::<ManifestApplyRequest, Vec<ManifestChange>>(c, "manifest", request)
+}
+
 /// get info about a specific package
 pub fn get_package(c: &Config, package: &str) -> Result<PackageInfo> {
     get::<PackageInfo>(c, &format!("package/{package}"))
@@ -105,7 +164,45 @@ where
     })
 }
 
+/// get the current build queue of the server
+pub fn get_queue(c: &Config) -> Result<BuildQueue> {
+    get::<BuildQueue>(c, "queue")
+}
+
 // get last used pkgbuild of package
 pub fn get_package_pkgbuild(c: &Config, package: &str) -> Result<String> {
     get::<String>(c, &format!("package/{package}/pkgbuild"))
 }
+
+/// get the diff of the build files of a package between two builds, by
+/// default from the built files to the next build
+pub fn get_package_diff(
+    c: &Config,
+    package: &str,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<String> {
+    let query = [from.map(|f| format!("from={f}")), to.map(|t| format!("to={t}"))]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let query = if query.is_empty() { query } else { format!("?{query}") };
+    get::<String>(c, &format!("package/{package}/diff{query}"))
+}
+
+/// get the changes of a package which await review
+pub fn get_package_review(c: &Config, package: &str) -> Result<PackageReview> {
+    get::<PackageReview>(c, &format!("package/{package}/review"))
+}
+
+/// approve or reject the changes of a package which await review
+pub fn review_package(c: &Config, package: &str, request: PackageReviewRequest) -> Result<()> {
+    post_simple(c, &format!("package/{package}/review"), request)
+}
+
+/// acknowledge the changed maintainer of a package
+pub fn trust_package(c: &Config, package: &str) -> Result<()> {
+    post_simple(c, &format!("package/{package}/trust"), ())
+}
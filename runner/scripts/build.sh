@@ -23,8 +23,11 @@ message "starting package build"
 FLAGS=$(cat makepkg-flags)
 echo "running with custom flags: $FLAGS"
 
-# run makepkg
-makepkg --force --noconfirm $FLAGS
+# run makepkg, stats are still collected if it fails (e.g. when running out of memory)
+makepkg --force --noconfirm $FLAGS || {
+  (. ../stats.sh > ../target/.stats-after.json)
+  exit 1
+}
 
 # collect stats after build
 (. ../stats.sh > ../target/.stats-after.json)
@@ -2,7 +2,7 @@
 
 # some aliases / functions
 message() {
-  printf "\n:: SERENE :: %s\n\n" "$1"
+  printf "\n:: SERENE :: %s :: %s\n\n" "$(date -u +%Y-%m-%dT%H:%M:%SZ)" "$1"
 }
 
 message "preparing container"
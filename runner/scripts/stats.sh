@@ -6,6 +6,10 @@ cd /sys/fs/cgroup
 # see https://docs.kernel.org/admin-guide/cgroup-v2.html#memory-interface-files
 if [ -f "memory.peak" ]; then MEMORY_PEAK=$(cat memory.peak); else MEMORY_PEAK="null"; fi
 
+# processes killed because the memory limit was reached
+# see https://docs.kernel.org/admin-guide/cgroup-v2.html#memory-interface-files
+if [ -f "memory.events" ]; then OOM_KILLS=$(cat memory.events | grep '^oom_kill ' | sed 's/ /\n/g' | sed -n '2 p'); else OOM_KILLS="null"; fi
+
 # user and system cpu time (us)
 # see https://docs.kernel.org/admin-guide/cgroup-v2.html#cpu-interface-files
 if [ -f "cpu.stat" ]; then USER_USEC=$(cat cpu.stat | grep user_usec | sed 's/ /\n/g' | sed -n '2 p'); else USER_USEC="null"; fi
@@ -27,4 +31,4 @@ else
     TOTAL_WBYTES="null"
 fi
 
-echo "{ \"memory_bytes_peak\": ${MEMORY_PEAK}, \"cpu_user_us\": ${USER_USEC}, \"cpu_system_us\": ${SYSTEM_USEC}, \"io_total_bytes_read\": ${TOTAL_RBYTES}, \"io_total_bytes_written\": ${TOTAL_WBYTES} }"
+echo "{ \"memory_bytes_peak\": ${MEMORY_PEAK}, \"cpu_user_us\": ${USER_USEC}, \"cpu_system_us\": ${SYSTEM_USEC}, \"io_total_bytes_read\": ${TOTAL_RBYTES}, \"io_total_bytes_written\": ${TOTAL_WBYTES}, \"oom_kills\": ${OOM_KILLS:-null} }"
@@ -35,6 +35,10 @@ pub enum BuildState {
     Failure,
     /// a fatal error occurred in a given step of the build
     Fatal(String, BuildProgress),
+    /// the build was stopped by a user in a given step of the build
+    Stopped(BuildProgress),
+    /// the build was held back until the changed build files are reviewed
+    Review,
 }
 
 impl BuildState {
@@ -44,7 +48,9 @@ impl BuildState {
             BuildState::Cancelled(_)
             | BuildState::Success
             | BuildState::Failure
-            | BuildState::Fatal(_, _) => true,
+            | BuildState::Fatal(_, _)
+            | BuildState::Stopped(_)
+            | BuildState::Review => true,
         }
     }
 }
@@ -75,9 +81,148 @@ pub struct BuildInfo {
     pub io_tbr: Option<usize>,
     /// total io bytes written
     pub io_tbw: Option<usize>,
+    /// amount of processes killed because the memory limit was reached
+    pub oom_kills: Option<usize>,
+
+    /// classification of the failure, if the build failed for a known reason
+    pub failure: Option<BuildFailure>,
+    /// commit which was pushed to trigger the build
+    pub commit: Option<String>,
+    /// findings of the static checks of the build files
+    #[serde(default)]
+    pub findings: Vec<LintFinding>,
+}
+
+/// category of a failed build, recognized from well-known messages in its logs
+#[derive(Clone, Copy, Serialize, Deserialize, EnumString, Display, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureCategory {
+    /// a source was signed with an unknown pgp key
+    UnknownKey,
+    /// the checksums of the sources did not match
+    Checksum,
+    /// a dependency could not be found in the repositories
+    MissingDependency,
+    /// the keyring of the container is out of date
+    Keyring,
+    /// the check() function of the package failed
+    Check,
+    /// a source could not be downloaded
+    Download,
+    /// the disk of the server ran out of space
+    DiskFull,
+}
+
+/// classification of a failed build
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BuildFailure {
+    /// category of the failure
+    pub category: FailureCategory,
+    /// hint on how the failure might be fixed
+    pub hint: String,
+}
+
+/// heuristic check which is run on the build files before a package is built
+#[derive(Clone, Copy, Serialize, Deserialize, EnumString, Display, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// something is downloaded and piped into a shell, e.g. `curl | sh`
+    PipeToShell,
+    /// a source is downloaded over plain http without a checksum
+    PlainHttp,
+    /// the checksum of a source which is not a vcs source is skipped
+    SkippedChecksum,
+    /// files are written outside of `$pkgdir` or `$srcdir`
+    OutsidePkgdir,
+    /// a long base64 blob is embedded or decoded
+    EncodedBlob,
+    /// a source is downloaded from a domain not used by the built version
+    NewDomain,
+}
+
+/// what happens to a build if a lint rule finds something
+#[derive(Clone, Copy, Serialize, Deserialize, EnumString, Display, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    /// the rule is not checked
+    Off,
+    /// the finding is shown with the build
+    Warn,
+    /// the package is not built
+    Block,
+}
+
+/// something suspicious found in the build files of a package
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LintFinding {
+    /// rule which found it
+    pub rule: LintRule,
+    /// severity the rule had
+    pub severity: LintSeverity,
+    /// file the finding is in
+    pub file: String,
+    /// what was found
+    pub message: String,
+}
+
+/// a section of the logs of a build, as marked by the runner
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BuildLogSection {
+    /// short name of the section, e.g. `build`
+    pub name: String,
+    /// message the runner printed when starting the section
+    pub message: String,
+    /// time the section was started, unknown for older builds
+    pub started: Option<DateTime<Utc>>,
+    /// duration of the section in seconds, if known
+    pub duration: Option<i64>,
+    /// amount of log lines in the section
+    pub lines: usize,
+}
+
+/// current contents of the build queue of the server
+#[derive(Serialize, Deserialize)]
+pub struct BuildQueue {
+    /// amount of builds which can run concurrently
+    pub slots: usize,
+    /// amount of builds currently occupying a slot
+    pub running: usize,
+    /// packages which are waiting for a free build slot, in order
+    pub waiting: Vec<String>,
+    /// packages which are locked by a running build session
+    pub locked: Vec<String>,
+    /// build sessions which are currently running
+    pub sessions: Vec<BuildQueueSession>,
+    /// upcoming scheduled builds
+    pub scheduled: Vec<BuildQueueTarget>,
+}
+
+/// state of a running build session
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BuildQueueSession {
+    /// reason the session was started
+    pub reason: BuildReason,
+    /// time the session was started
+    pub started: DateTime<Utc>,
+    /// packages which are currently building
+    pub building: Vec<String>,
+    /// packages which did not start yet, with the dependencies they wait for
+    pub pending: Vec<(String, Vec<String>)>,
+}
+
+/// a scheduled target at which packages will be built
+#[derive(Serialize, Deserialize)]
+pub struct BuildQueueTarget {
+    /// time at which the target is reached
+    pub time: DateTime<Utc>,
+    /// packages which will be built
+    pub packages: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, EnumString, Display, Clone, Copy)]
+#[derive(Serialize, Deserialize, EnumString, Display, Clone, Copy, PartialEq, Eq, Hash)]
 #[strum(serialize_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum BuildReason {
@@ -89,6 +234,8 @@ pub enum BuildReason {
     Schedule,
     /// initial build of package after addition
     Initial,
+    /// clean retry of a failed build
+    Retry,
     /// build reason is not known
     ///
     /// only here for compatibility with older versions
@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 pub mod build;
+pub mod manifest;
 pub mod package;
 pub mod secret;
 
@@ -20,3 +21,25 @@ pub struct SereneInfo {
     /// are the packages signed
     pub signed: bool,
 }
+
+/// action performed on the server, as recorded in the audit log
+#[derive(Serialize, Deserialize)]
+pub struct AuditInfo {
+    /// when the action was performed
+    pub time: DateTime<Utc>,
+    /// comment of the secret which performed it, usually the user and host
+    pub secret: String,
+    /// method and path of the request
+    pub endpoint: String,
+    /// json body of the request, with private values redacted
+    pub body: Option<String>,
+}
+
+/// result of purging old builds
+#[derive(Serialize, Deserialize)]
+pub struct PurgeInfo {
+    /// amount of builds that were removed
+    pub builds: usize,
+    /// amount of bytes freed by removing logs
+    pub freed: u64,
+}
@@ -0,0 +1,77 @@
+use crate::package::{MakepkgFlag, PackageAddSource};
+use serde::{Deserialize, Serialize};
+
+/// placeholder for prepare commands of private packages, which are not
+/// exported. applying it keeps the prepare commands of the package as they are
+pub const REDACTED_PREPARE: &str = "<redacted>";
+
+fn true_default() -> bool {
+    true
+}
+
+/// declarative description of the packages added to a server
+#[derive(Serialize, Deserialize, Default)]
+pub struct Manifest {
+    /// packages which should be added
+    #[serde(default)]
+    pub packages: Vec<ManifestPackage>,
+}
+
+/// a package and its settings inside a manifest
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestPackage {
+    /// base of the package
+    pub base: String,
+    /// where the package is added from, the devel flag inside is ignored
+    pub source: PackageAddSource,
+
+    /// is the package a devel package
+    #[serde(default)]
+    pub devel: bool,
+    /// is the package enabled, meaning it is built automatically
+    #[serde(default = "true_default")]
+    pub enabled: bool,
+    /// should the package be cleaned after building
+    #[serde(default)]
+    pub clean: bool,
+    /// does the package contain private information
+    #[serde(default)]
+    pub private: bool,
+    /// custom cron schedule of the package, the default one if empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    /// commands to run before the build
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prepare: Option<String>,
+    /// special makepkg flags
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<MakepkgFlag>,
+    /// do changes to the build files have to be reviewed before building
+    #[serde(default)]
+    pub review: bool,
+}
+
+/// request to apply a manifest to the server
+#[derive(Serialize, Deserialize)]
+pub struct ManifestApplyRequest {
+    /// manifest to apply
+    pub manifest: Manifest,
+    /// only calculate the changes, without applying them
+    pub dry_run: bool,
+    /// remove packages which are not part of the manifest
+    pub prune: bool,
+}
+
+/// change which is made to the server by applying a manifest
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum ManifestChange {
+    /// the package is added
+    Add { base: String },
+    /// the package is added again from another source
+    Replace { base: String },
+    /// a setting of the package is changed
+    Set { base: String, setting: String, old: String, new: String },
+    /// the package is removed
+    Remove { base: String },
+}
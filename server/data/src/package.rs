@@ -21,7 +21,7 @@ pub struct PackageAddRequest {
     pub source: PackageAddSource,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum PackageAddSource {
     Aur { name: String },
@@ -41,6 +41,43 @@ pub enum PackageSettingsRequest {
     Flags(Vec<MakepkgFlag>),
     Devel(bool),
     SrcinfoOverride(bool),
+    Timeout(Option<u32>),
+    Limits(ResourceLimits),
+    Notify(Option<NotifyRule>),
+    Review(bool),
+}
+
+/// rule on which build results notifications are sent
+#[derive(Serialize, Deserialize, EnumString, Display, Clone, Copy, PartialEq, Eq, Debug)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyRule {
+    /// never send notifications
+    Never,
+    /// notify when a build fails
+    Failure,
+    /// notify when a build fails and when it succeeds again after failing
+    Recovery,
+    /// notify on every finished build, whether it failed or succeeded
+    Always,
+}
+
+/// resource limits for the build container of a package
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// memory limit in mebibytes
+    pub memory: Option<u64>,
+    /// amount of cpus the build can use
+    pub cpus: Option<f32>,
+    /// maximal amount of processes in the container
+    pub pids: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// are no limits set at all
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_none() && self.cpus.is_none() && self.pids.is_none()
+    }
 }
 
 /// parameters for requesting package builds
@@ -71,7 +108,7 @@ impl PackageBuildRequest {
 /// All supported makepkg flags which make sense to supply. Name the enum
 /// entries just like the args (caseinsenitive). See `makepkg --help` for these
 /// args
-#[derive(Serialize, Deserialize, EnumString, Display, Clone)]
+#[derive(Serialize, Deserialize, EnumString, Display, Clone, PartialEq)]
 #[strum(serialize_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum MakepkgFlag {
@@ -121,6 +158,8 @@ pub struct PackagePeek {
 
     /// latest build of the package
     pub build: Option<BuildInfo>,
+    /// unacknowledged change of the aur maintainer
+    pub maintainer_change: Option<MaintainerChange>,
 
     /// date added
     pub added: DateTime<Utc>,
@@ -167,6 +206,28 @@ pub struct PackageInfo {
     pub prepare_commands: Option<String>,
     /// makepkg flags
     pub makepkg_flags: Vec<MakepkgFlag>,
+    /// build timeout of the package in minutes
+    pub timeout: Option<u32>,
+    /// timeout of the package was changed
+    pub timeout_changed: bool,
+    /// resource limits of the build container
+    pub limits: ResourceLimits,
+    /// resource limits of the package were changed
+    pub limits_changed: bool,
+    /// rule on which notifications are sent for the package
+    pub notify: NotifyRule,
+    /// notification rule of the package was changed
+    pub notify_changed: bool,
+    /// when a webhook for the package was last received
+    pub webhook_fired: Option<DateTime<Utc>>,
+    /// do changes to the build files have to be approved before building
+    pub review: bool,
+    /// are changes to the build files awaiting review
+    pub review_pending: bool,
+    /// unacknowledged change of the aur maintainer
+    pub maintainer_change: Option<MaintainerChange>,
+    /// whether changes of the aur maintainer are detected, none for other sources
+    pub maintainer_tracked: Option<bool>,
 
     /// date added
     pub added: DateTime<Utc>,
@@ -182,6 +243,12 @@ pub enum BroadcastEvent {
     Log(String),
     /// Ping to the event subscriber
     Ping,
+    /// Package was added to the server
+    Add,
+    /// Package was removed from the server
+    Remove,
+    /// Settings of the package were changed
+    Settings,
 }
 
 impl BroadcastEvent {
@@ -191,6 +258,45 @@ impl BroadcastEvent {
             BroadcastEvent::Change { .. } => "change",
             BroadcastEvent::Log { .. } => "log",
             BroadcastEvent::Ping => "ping",
+            BroadcastEvent::Add => "add",
+            BroadcastEvent::Remove => "remove",
+            BroadcastEvent::Settings => "settings",
         }
     }
 }
+
+/// change of the maintainer of an aur package, which disables automatic
+/// building until it is acknowledged
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MaintainerChange {
+    /// acknowledged maintainer, none if the package was orphaned
+    pub previous: Option<String>,
+    /// current maintainer, none if the package is orphaned
+    pub current: Option<String>,
+    /// when the change was detected
+    pub detected: DateTime<Utc>,
+}
+
+impl MaintainerChange {
+    /// describes the change for humans
+    pub fn describe(&self) -> String {
+        let name = |m: &Option<String>| m.clone().unwrap_or_else(|| "nobody (orphan)".to_string());
+        format!("maintainer changed from {} to {}", name(&self.previous), name(&self.current))
+    }
+}
+
+/// changes to the build files of a package which await review
+#[derive(Serialize, Deserialize)]
+pub struct PackageReview {
+    /// when the changes were found
+    pub created: DateTime<Utc>,
+    /// unified diff of the build files against the last built ones
+    pub diff: String,
+}
+
+/// decision about changes which await review
+#[derive(Serialize, Deserialize)]
+pub struct PackageReviewRequest {
+    /// approve the changes and build them, otherwise they are rejected
+    pub approve: bool,
+}
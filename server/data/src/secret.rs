@@ -1,6 +1,63 @@
 use base64::prelude::{BASE64_STANDARD, BASE64_URL_SAFE};
 use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use strum_macros::{Display, EnumString};
+
+/// scope which grants a secret access to a part of the api
+#[derive(Serialize, Deserialize, EnumString, Display, Clone, Copy, PartialEq, Eq, Debug)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// read packages, builds and logs
+    Read,
+    /// trigger and stop builds, and issue webhook secrets
+    Build,
+    /// add and remove packages and change their settings
+    Manage,
+    /// everything, including server maintenance like purging
+    Admin,
+}
+
+impl Scope {
+    /// returns whether this scope grants the given one
+    pub fn grants(&self, scope: Scope) -> bool {
+        *self == Scope::Admin || *self == scope
+    }
+}
+
+/// information about an authorized secret
+#[derive(Serialize, Deserialize)]
+pub struct SecretInfo {
+    /// id used to refer to the secret
+    pub id: i64,
+    /// hash of the secret
+    pub hash: String,
+    /// comment of the secret, usually the user and host it belongs to
+    pub comment: String,
+    /// scopes the secret was granted
+    pub scopes: Vec<Scope>,
+    /// globs of the package bases the secret may access, empty if unrestricted
+    pub packages: Vec<String>,
+    /// when the secret was authorized
+    pub created: DateTime<Utc>,
+    /// when the secret was last used
+    pub used: Option<DateTime<Utc>>,
+}
+
+/// request to authorize a new secret
+#[derive(Serialize, Deserialize)]
+pub struct SecretAddRequest {
+    /// hash of the secret, as printed by the cli
+    pub hash: String,
+    /// comment of the secret, usually the user and host it belongs to
+    pub comment: String,
+    /// scopes to grant the secret
+    pub scopes: Vec<Scope>,
+    /// globs of the package bases the secret may access, empty if unrestricted
+    pub packages: Vec<String>,
+}
 
 /// hashes a secret and converts it to string, the way it is in
 /// authorized_secrets
@@ -20,3 +77,9 @@ pub fn hash_url_safe(secret: &str) -> String {
 
     BASE64_URL_SAFE.encode(hasher.finalize())
 }
+
+/// checks whether a string looks like a hashed secret, to catch users
+/// authorizing plain secrets by accident
+pub fn is_hash(hash: &str) -> bool {
+    BASE64_STANDARD.decode(hash).is_ok_and(|bytes| bytes.len() == Sha256::output_size())
+}
@@ -0,0 +1,2 @@
+-- timeout in minutes, default if null
+ALTER TABLE package ADD COLUMN timeout INTEGER;
@@ -0,0 +1,2 @@
+ALTER TABLE package ADD COLUMN limits TEXT;
+ALTER TABLE build ADD COLUMN oom_kills INTEGER;
@@ -0,0 +1,3 @@
+ALTER TABLE build ADD COLUMN resolve BOOLEAN;
+ALTER TABLE build ADD COLUMN clean BOOLEAN;
+ALTER TABLE build ADD COLUMN force BOOLEAN;
@@ -0,0 +1,2 @@
+ALTER TABLE build ADD COLUMN failure TEXT;
+ALTER TABLE build ADD COLUMN failure_hint TEXT;
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS secret
+(
+    id       INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    hash     VARCHAR  NOT NULL UNIQUE,
+
+    -- usually the user and host the secret belongs to
+    comment  VARCHAR  NOT NULL,
+
+    -- comma separated
+    scopes   VARCHAR  NOT NULL,
+    packages VARCHAR,
+
+    created  DATETIME NOT NULL,
+    used     DATETIME,
+
+    -- revoked secrets are kept, so they are not imported again
+    revoked  DATETIME
+)
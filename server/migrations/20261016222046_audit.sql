@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS audit
+(
+    id       INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    time     DATETIME NOT NULL,
+
+    -- comment of the secret which performed the action, usually user@host
+    secret   VARCHAR  NOT NULL,
+
+    -- method and path of the request
+    endpoint VARCHAR  NOT NULL,
+
+    -- json, with private values redacted
+    body     VARCHAR
+)
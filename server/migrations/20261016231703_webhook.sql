@@ -0,0 +1,2 @@
+ALTER TABLE package ADD COLUMN webhook_nonce TEXT;
+ALTER TABLE package ADD COLUMN webhook_fired DATETIME;
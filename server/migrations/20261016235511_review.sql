@@ -0,0 +1,5 @@
+ALTER TABLE package ADD COLUMN review BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE package ADD COLUMN built_files TEXT;
+ALTER TABLE package ADD COLUMN review_pending TEXT;
+ALTER TABLE package ADD COLUMN review_approved TEXT;
+ALTER TABLE package ADD COLUMN review_rejected TEXT;
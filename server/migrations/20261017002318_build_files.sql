@@ -0,0 +1 @@
+ALTER TABLE build ADD COLUMN files TEXT;
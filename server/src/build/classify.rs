@@ -0,0 +1,95 @@
+use serene_data::build::{BuildFailure, FailureCategory};
+
+/// returns the rest of the first line in the logs after the given message
+fn find<'a>(logs: &'a str, message: &str) -> Option<&'a str> {
+    logs.lines().find_map(|line| line.split_once(message).map(|(_, rest)| rest.trim()))
+}
+
+/// returns whether the logs contain the given message
+fn contains(logs: &str, message: &str) -> bool {
+    find(logs, message).is_some()
+}
+
+/// tries to find out why a build failed by looking for well-known failure
+/// messages of makepkg and pacman in its logs
+pub fn classify(package: &str, logs: &str) -> Option<BuildFailure> {
+    let failure = |category, hint: String| Some(BuildFailure { category, hint });
+
+    // checked first, as it causes all sorts of other failures
+    if contains(logs, "No space left on device") {
+        return failure(
+            FailureCategory::DiskFull,
+            "the server ran out of disk space during the build, free some up, e.g. by purging old builds with `serene server purge`".to_string(),
+        );
+    }
+
+    if contains(logs, "is unknown trust")
+        || contains(logs, "invalid or corrupted package (PGP signature)")
+    {
+        return failure(
+            FailureCategory::Keyring,
+            format!(
+                "the keyring of the build container is out of date, build again in a fresh container with `serene build {package} --clean`"
+            ),
+        );
+    }
+
+    if let Some(key) = find(logs, "unknown public key ") {
+        let key = key.trim_end_matches(')');
+
+        return failure(
+            FailureCategory::UnknownKey,
+            format!(
+                "the sources are signed with the unknown pgp key {key}, import it before building with `serene info {package} set prepare \"gpg --recv-keys {key}\"`"
+            ),
+        );
+    }
+
+    if contains(logs, "The requested URL returned error: 404") {
+        return failure(
+            FailureCategory::Download,
+            "a source could not be downloaded as it does not exist (404), its url has probably changed upstream".to_string(),
+        );
+    }
+
+    if contains(logs, "Failure while downloading") {
+        return failure(
+            FailureCategory::Download,
+            "a source could not be downloaded, check whether its url is still reachable"
+                .to_string(),
+        );
+    }
+
+    if contains(logs, "One or more files did not pass the validity check!") {
+        return failure(
+            FailureCategory::Checksum,
+            "the checksums of the sources do not match, they have probably changed upstream without the PKGBUILD being updated".to_string(),
+        );
+    }
+
+    let missing = logs
+        .lines()
+        .filter_map(|line| line.split_once("error: target not found: ").map(|(_, t)| t.trim()))
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        return failure(
+            FailureCategory::MissingDependency,
+            format!(
+                "the dependencies {} could not be found in the repositories, if they are from the AUR, add them to serene with `serene add`",
+                missing.join(", ")
+            ),
+        );
+    }
+
+    if contains(logs, "A failure occurred in check()") {
+        return failure(
+            FailureCategory::Check,
+            format!(
+                "the tests in check() failed, skip them with `serene info {package} set flags nocheck` if they are known to be flaky"
+            ),
+        );
+    }
+
+    None
+}
@@ -1,19 +1,29 @@
+use crate::build::schedule::BuildMeta;
 use crate::config::CONFIG;
 use crate::database::{self, Database};
-use crate::package::Package;
 use crate::package::srcinfo::SrcinfoGeneratorInstance;
+use crate::package::{Package, files, lint};
 use crate::repository::PackageRepositoryInstance;
 use crate::runner::stats::CgroupStats;
 use crate::runner::{ContainerId, RunStatus, RunnerInstance};
 use crate::web::broadcast::BroadcastInstance;
+use crate::web::metrics::METRICS;
 use chrono::{DateTime, Utc};
-use log::{info, warn};
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use serene_data::build::BuildProgress::{Build, Clean, Publish, Update};
-use serene_data::build::BuildState::{Failure, Fatal, Running, Success};
-use serene_data::build::{BuildProgress, BuildReason, BuildState};
+use serene_data::build::BuildProgress::{Build, Clean, Publish, Resolve, Update};
+use serene_data::build::BuildState::{Failure, Fatal, Running, Stopped, Success};
+use serene_data::build::{
+    BuildFailure, BuildProgress, BuildReason, BuildState, LintFinding, LintSeverity,
+};
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit, broadcast};
 
+pub mod classify;
+pub mod notify;
 pub mod schedule;
 pub mod session;
 
@@ -25,6 +35,8 @@ pub struct BuildSummary {
     pub state: BuildState,
     /// reason why the build ran
     pub reason: BuildReason,
+    /// metadata the build was started with, unknown for older builds
+    pub meta: Option<BuildMeta>,
 
     /// logs / status obtained from the build container
     pub details: Option<RunStatus>,
@@ -39,19 +51,27 @@ pub struct BuildSummary {
     /// container cgroup stats of the build
     #[serde(flatten)]
     pub stats: Option<CgroupStats>,
+
+    /// classification of the failure if the build failed
+    pub failure: Option<BuildFailure>,
+    /// findings of the static checks of the build files
+    pub findings: Vec<LintFinding>,
 }
 
 impl BuildSummary {
-    pub fn start(package: &Package, reason: BuildReason) -> Self {
+    pub fn start(package: &Package, meta: BuildMeta) -> Self {
         Self {
             package: package.base.clone(),
+            reason: meta.reason,
+            meta: Some(meta),
             state: BuildState::Pending,
             details: None,
             version: None,
             started: Utc::now(),
             ended: None,
             stats: None,
-            reason,
+            failure: None,
+            findings: vec![],
         }
     }
 
@@ -62,27 +82,35 @@ impl BuildSummary {
 }
 
 /// cleans up builds which are pending or working, but serene exited in the
-/// meantime, or some beyond fatal error happened
-pub async fn cleanup_unfinished(db: &Database) -> anyhow::Result<()> {
+/// meantime, or some beyond fatal error happened, returns the cleaned builds
+pub async fn cleanup_unfinished(db: &Database) -> anyhow::Result<Vec<BuildSummary>> {
     info!("checking for unfinished builds");
 
     let active = BuildSummary::find_active(db).await?;
+    let mut cleaned = vec![];
 
     for mut summary in active {
         warn!("cleaning build for {}, as it is still active", summary.package);
 
+        let message = if CONFIG.requeue_unfinished {
+            "build was not finished because serene was restarted, it was requeued".to_owned()
+        } else {
+            "build was not finished or failed beyond fatally, then serene was restarted - check your logs!".to_owned()
+        };
+
         summary.end(Fatal(
-            "build was not finished or failed beyond fatally, then serene was restarted - check your logs!".to_owned(),
-            if let Running(state) = &summary.state { *state } else { BuildProgress::Resolve }
+            message,
+            if let Running(state) = &summary.state { *state } else { BuildProgress::Resolve },
         ));
 
         // we set the time to zero so we don't have stupidly long time durations
         summary.ended = Some(summary.started);
 
         summary.change(db).await?;
+        cleaned.push(summary);
     }
 
-    Ok(())
+    Ok(cleaned)
 }
 
 pub type BuilderInstance = Arc<Builder>;
@@ -93,6 +121,16 @@ pub struct Builder {
     broadcast: BroadcastInstance,
     repository: PackageRepositoryInstance,
     srcinfo_generator: SrcinfoGeneratorInstance,
+
+    /// packages which a user requested to be stopped
+    stopping: Mutex<HashSet<String>>,
+    /// sends the base of a package when it is requested to be stopped
+    stop_signal: broadcast::Sender<String>,
+
+    /// slots for builds running concurrently on the whole server
+    slots: Semaphore,
+    /// packages waiting for a free build slot, in order
+    queue: Mutex<Vec<String>>,
 }
 
 impl Builder {
@@ -104,7 +142,94 @@ impl Builder {
         broadcast: BroadcastInstance,
         srcinfo_generator: SrcinfoGeneratorInstance,
     ) -> Self {
-        Self { db, runner, repository, broadcast, srcinfo_generator }
+        Self {
+            db,
+            runner,
+            repository,
+            broadcast,
+            srcinfo_generator,
+            stopping: Mutex::new(HashSet::new()),
+            stop_signal: broadcast::channel(16).0,
+            slots: Semaphore::new(CONFIG.concurrent_builds),
+            queue: Mutex::new(vec![]),
+        }
+    }
+
+    /// requests the build of a package to be stopped, kills its container if it
+    /// is currently building
+    pub async fn stop(&self, package: &Package) -> anyhow::Result<()> {
+        info!("stopping build for package {}", package.base);
+
+        self.stopping.lock().await.insert(package.base.clone());
+        // nobody might be listening, which is fine
+        let _ = self.stop_signal.send(package.base.clone());
+
+        self.runner.kill_build_container(package).await
+    }
+
+    /// returns whether the build of a package was requested to be stopped
+    pub async fn is_stopping(&self, base: &str) -> bool {
+        self.stopping.lock().await.contains(base)
+    }
+
+    /// takes the stop request of a package, returns whether it was present
+    pub async fn take_stopping(&self, base: &str) -> bool {
+        self.stopping.lock().await.remove(base)
+    }
+
+    /// subscribes to the bases of packages which are requested to be stopped
+    pub fn subscribe_stopping(&self) -> broadcast::Receiver<String> {
+        self.stop_signal.subscribe()
+    }
+
+    /// returns the packages which are waiting for a free build slot, in order
+    pub async fn queued(&self) -> Vec<String> {
+        self.queue.lock().await.clone()
+    }
+
+    /// returns the amount of build slots which are currently occupied
+    pub fn running(&self) -> usize {
+        CONFIG.concurrent_builds.saturating_sub(self.slots.available_permits())
+    }
+
+    /// waits for a free build slot for a package, returns none if the build was
+    /// stopped in the meantime
+    async fn acquire_slot(&self, base: &str) -> Option<SemaphorePermit<'_>> {
+        let mut stops = self.subscribe_stopping();
+
+        if let Ok(permit) = self.slots.try_acquire() {
+            return Some(permit);
+        }
+
+        let ahead = {
+            let mut queue = self.queue.lock().await;
+            queue.push(base.to_owned());
+            queue.len() - 1
+        };
+
+        info!("build for package {base} is waiting for a free build slot");
+        self.broadcast
+            .log(base, format!("waiting for a free build slot, {ahead} builds are queued before\n"))
+            .await;
+
+        // the same future is polled throughout, so the place in the queue of the
+        // semaphore is kept when other builds are stopped
+        let acquire = self.slots.acquire();
+        tokio::pin!(acquire);
+
+        let permit = loop {
+            select! {
+                permit = &mut acquire => break permit.ok(),
+                _ = stops.recv() => {
+                    if self.is_stopping(base).await {
+                        break None;
+                    }
+                }
+            }
+        };
+
+        self.queue.lock().await.retain(|b| b != base);
+        permit
     }
 
     /// Removes a package from the system, by removing the container, from the
@@ -135,7 +260,21 @@ impl Builder {
         force_clean: bool,
         mut summary: BuildSummary,
     ) -> anyhow::Result<BuildSummary> {
+        // the slot is held until the build is finished
+        let slot = self.acquire_slot(&package.base).await;
+
+        // force_clean is only used here as it is intended to remove the _old_ container
+        let clean = package.clean || CONFIG.force_clean || force_clean;
+
         let state = 'run: {
+            if slot.is_none() {
+                break 'run Stopped(Resolve);
+            }
+
+            if self.is_stopping(&package.base).await {
+                break 'run Stopped(if update { Update } else { Build });
+            }
+
             // UPDATE
             if update {
                 summary.state = Running(Update);
@@ -150,22 +289,78 @@ impl Builder {
                 };
             }
 
+            if self.is_stopping(&package.base).await {
+                break 'run Stopped(Build);
+            }
+
+            // remember the build files, so builds can be compared later
+            let unlinted = match files::read(&package).await {
+                Ok(files) => {
+                    summary.change_files(&files, &self.db).await?;
+
+                    match lint::check(&package, &files).await {
+                        Ok(findings) => {
+                            summary.findings = findings;
+                            None
+                        }
+                        Err(e) => Some(format!("failed to lint build files: {e:#}")),
+                    }
+                }
+                Err(e) => Some(format!("failed to read build files: {e:#}")),
+            };
+
+            if let Some(e) = unlinted {
+                // blocking rules can't be enforced without linting
+                if CONFIG.lint_blocks() {
+                    break 'run Fatal(e, Build);
+                }
+
+                warn!("build of {} is not linted, {e}", package.base);
+            }
+
+            let blocking = summary
+                .findings
+                .iter()
+                .filter(|f| f.severity == LintSeverity::Block)
+                .map(|f| f.rule.to_string())
+                .collect::<Vec<_>>();
+
+            if !blocking.is_empty() {
+                break 'run Fatal(
+                    format!("build files were blocked by lint rules: {}", blocking.join(", ")),
+                    Build,
+                );
+            }
+
             // BUILD
             summary.state = Running(Build);
             summary.change(&self.db).await?;
             self.broadcast.change(&package.base, summary.state.clone()).await;
 
-            // force_clean is only used here as it is intended to remove the _old_ container
-            let clean = package.clean || CONFIG.force_clean || force_clean;
-
             let (container, success) = match self.build(&mut package, clean).await {
                 Ok((status, logs, container)) => {
                     let next = status.success;
+                    let timed_out = status.timed_out;
                     summary.details = Some(status);
 
+                    // look for known failure reasons before the logs are gone
+                    if !next {
+                        summary.failure = classify::classify(&package.base, &logs);
+                    }
+
                     // write logs to disk
                     database::log::write(&summary, logs).await?;
 
+                    if timed_out {
+                        break 'run Fatal(
+                            format!(
+                                "build timed out after {} minutes",
+                                package.get_timeout().unwrap_or_default()
+                            ),
+                            Build,
+                        );
+                    }
+
                     (container, next)
                 }
                 Err(e) => {
@@ -173,8 +368,19 @@ impl Builder {
                 }
             };
 
+            // the container might have been killed, we don't publish in any case
+            let stopped = self.is_stopping(&package.base).await;
+
+            // failed builds still have stats, which tell whether they ran out of memory
+            if !success && !stopped {
+                match self.failure_stats(&container).await {
+                    Ok(build_stats) => summary.stats = Some(build_stats),
+                    Err(e) => debug!("no stats for failed build of {}: {e:#}", package.base),
+                }
+            }
+
             // PUBLISH
-            if success {
+            if success && !stopped {
                 summary.state = Running(Publish);
                 summary.change(&self.db).await?;
                 self.broadcast.change(&package.base, summary.state.clone()).await;
@@ -210,12 +416,54 @@ impl Builder {
                 }
             }
 
-            if success { Success } else { Failure }
+            if stopped {
+                Stopped(Build)
+            } else if success {
+                Success
+            } else if let Some(kills) =
+                summary.stats.as_ref().and_then(|s| s.oom_kills).filter(|k| *k > 0)
+            {
+                let limit =
+                    package.get_limits().memory.map(|m| format!(" of {m} MiB")).unwrap_or_default();
+
+                Fatal(
+                    format!("build ran out of memory{limit}, {kills} processes were killed"),
+                    Build,
+                )
+            } else {
+                Failure
+            }
         };
 
+        // the classification only explains ordinary failures
+        if !matches!(state, Failure) {
+            summary.failure = None;
+        }
+
         summary.end(state);
         summary.change(&self.db).await?;
         self.broadcast.change(&package.base, summary.state.clone()).await;
+        METRICS.build_finished(&summary);
+
+        // the reused container might have been stale, so we try again in a fresh one
+        if CONFIG.retry_clean && !clean && matches!(summary.state, Failure) {
+            info!("retrying failed build for package {} in a clean container", package.base);
+
+            // the retry has to wait for a slot again
+            drop(slot);
+
+            let meta = summary.meta.clone().unwrap_or_else(|| BuildMeta::normal(summary.reason));
+            let retry = BuildSummary::start(
+                &package,
+                BuildMeta { reason: BuildReason::Retry, clean: true, ..meta },
+            );
+            retry.save(&self.db).await?;
+            self.broadcast.change(&package.base, retry.state.clone()).await;
+
+            return Box::pin(self.run_build(package, false, true, retry)).await;
+        }
+
+        notify::build_finished(&self.db, &package, &summary).await;
 
         Ok(summary)
     }
@@ -235,7 +483,9 @@ impl Builder {
 
         self.runner.upload_inputs(&container, package.build_files().await?).await?;
 
-        let (status, logs) = self.runner.run(&container, Some(package.base.clone())).await?;
+        let timeout = package.get_timeout().map(|t| Duration::from_secs(t as u64 * 60));
+        let (status, logs) =
+            self.runner.run(&container, Some(package.base.clone()), timeout).await?;
 
         Ok((status, logs, container))
     }
@@ -258,6 +508,14 @@ impl Builder {
         Ok(build_stats)
     }
 
+    /// reads the stats of a failed build from a given container
+    async fn failure_stats(&self, container: &ContainerId) -> anyhow::Result<CgroupStats> {
+        let mut output = self.runner.download_outputs(container).await?;
+
+        let (stats_before, stats_after) = output.build_stats().await?;
+        Ok(stats_after - stats_before)
+    }
+
     /// cleans a given container
     async fn clean(&self, container: &ContainerId) -> anyhow::Result<()> {
         self.runner.clean(container).await
@@ -0,0 +1,212 @@
+use crate::build::BuildSummary;
+use crate::config::CONFIG;
+use crate::database::Database;
+use crate::package::Package;
+use anyhow::{Context, Result, anyhow};
+use log::{debug, warn};
+use reqwest::Client;
+use serde::Serialize;
+use serene_data::build::{BuildInfo, BuildReason, BuildState};
+use serene_data::package::NotifyRule;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// kind of build result a notification is sent for
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum NotifyEvent {
+    /// the build failed
+    Failure,
+    /// the build succeeded after the previous one failed
+    Recovery,
+    /// the build succeeded
+    Success,
+    /// the build is held until changes to the build files are reviewed
+    Review,
+}
+
+impl NotifyEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            NotifyEvent::Failure => "failure",
+            NotifyEvent::Recovery => "recovery",
+            NotifyEvent::Success => "success",
+            NotifyEvent::Review => "review",
+        }
+    }
+}
+
+/// notification which is sent to the configured targets
+#[derive(Serialize)]
+struct Notification {
+    /// what happened to the build
+    event: NotifyEvent,
+    /// short title, e.g. for gotify or ntfy
+    title: String,
+    /// human readable description of the result
+    message: String,
+    /// base of the built package
+    package: String,
+    /// the finished build
+    build: BuildInfo,
+}
+
+impl Notification {
+    fn new(event: NotifyEvent, summary: &BuildSummary) -> Self {
+        let package = &summary.package;
+
+        let title = match event {
+            NotifyEvent::Failure => format!("{package} failed to build"),
+            NotifyEvent::Recovery => format!("{package} builds again"),
+            NotifyEvent::Success => format!("{package} was built"),
+            NotifyEvent::Review => format!("{package} awaits review"),
+        };
+
+        let message = match &summary.state {
+            BuildState::Fatal(message, progress) => format!("fatal error on {progress}: {message}"),
+            BuildState::Review => {
+                format!("build files changed, review them with `serene review {package}`")
+            }
+            BuildState::Failure => summary
+                .failure
+                .as_ref()
+                .map(|f| format!("{}: {}", f.category, f.hint))
+                .unwrap_or_else(|| "see the logs for error messages".to_string()),
+            _ => format!(
+                "version {} is now available",
+                summary.version.as_deref().unwrap_or("unknown")
+            ),
+        };
+
+        Self { event, title, message, package: package.clone(), build: summary.as_info() }
+    }
+}
+
+/// determines whether a notification should be sent for the finished build
+async fn event(
+    db: &Database,
+    package: &Package,
+    summary: &BuildSummary,
+) -> Result<Option<NotifyEvent>> {
+    let rule = package.get_notify();
+
+    match summary.state {
+        BuildState::Failure | BuildState::Fatal(_, _) => {
+            Ok((rule != NotifyRule::Never).then_some(NotifyEvent::Failure))
+        }
+        // nothing is built until somebody looks at the changes
+        BuildState::Review => Ok((rule != NotifyRule::Never).then_some(NotifyEvent::Review)),
+        BuildState::Success => {
+            if rule == NotifyRule::Never || rule == NotifyRule::Failure {
+                return Ok(None);
+            }
+
+            // a clean retry replaces the failed attempt before it, which was never notified
+            let skip = if summary.reason == BuildReason::Retry { 1 } else { 0 };
+
+            // the latest build is the current one
+            let previous = BuildSummary::find_latest_n_for_package(&package.base, 3, db)
+                .await?
+                .into_iter()
+                .filter(|b| b.started != summary.started)
+                .nth(skip);
+
+            if previous
+                .is_some_and(|b| matches!(b.state, BuildState::Failure | BuildState::Fatal(_, _)))
+            {
+                Ok(Some(NotifyEvent::Recovery))
+            } else if rule == NotifyRule::Always {
+                Ok(Some(NotifyEvent::Success))
+            } else {
+                Ok(None)
+            }
+        }
+        // stopped builds are caused by the user anyway
+        _ => Ok(None),
+    }
+}
+
+/// posts the notification as json to the given url
+async fn post(url: &str, body: &str) -> Result<()> {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .context("failed to build notification client")?
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(body.to_owned())
+        .send()
+        .await
+        .context("failed to send notification")?
+        .error_for_status()
+        .context("notification target returned an error")?;
+
+    Ok(())
+}
+
+/// runs the shell command with the notification in its environment
+async fn run(command: &str, notification: &Notification, body: &str) -> Result<()> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("SERENE_EVENT", notification.event.name())
+        .env("SERENE_PACKAGE", &notification.package)
+        .env("SERENE_TITLE", &notification.title)
+        .env("SERENE_MESSAGE", &notification.message)
+        .env("SERENE_NOTIFICATION", body)
+        .output()
+        .await
+        .context("failed to run notification command")?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "notification command failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// sends notifications for a finished build to the configured targets if the
+/// rule of the package asks for it, sending happens in the background
+pub async fn build_finished(db: &Database, package: &Package, summary: &BuildSummary) {
+    if CONFIG.notify_url.is_none() && CONFIG.notify_command.is_none() {
+        return;
+    }
+
+    let event = match event(db, package, summary).await {
+        Ok(Some(event)) => event,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("failed to determine notification for build of {}: {e:#}", package.base);
+            return;
+        }
+    };
+
+    let notification = Notification::new(event, summary);
+
+    tokio::spawn(async move {
+        debug!("sending notification for build of {}", notification.package);
+
+        let body = match serde_json::to_string(&notification) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("failed to serialize notification: {e:#}");
+                return;
+            }
+        };
+
+        if let Some(url) = &CONFIG.notify_url
+            && let Err(e) = post(url, &body).await
+        {
+            warn!("failed to post notification for {}: {e:#}", notification.package);
+        }
+
+        if let Some(command) = &CONFIG.notify_command
+            && let Err(e) = run(command, &notification, &body).await
+        {
+            warn!("failed to run notification command for {}: {e:#}", notification.package);
+        }
+    });
+}
@@ -1,6 +1,8 @@
-use crate::build::session::BuildSession;
-use crate::build::BuilderInstance;
+use crate::build::session::{BuildSession, SessionRegistryInstance};
+use crate::build::{BuildSummary, BuilderInstance, notify};
+use crate::config::CONFIG;
 use crate::database::Database;
+use crate::package::review::{self, ReviewOutcome};
 use crate::package::srcinfo::SrcinfoGeneratorInstance;
 use crate::package::Package;
 use crate::web::broadcast::BroadcastInstance;
@@ -8,7 +10,8 @@ use anyhow::{anyhow, Context};
 use chrono::{DateTime, Utc};
 use cron::Schedule;
 use log::{debug, error, info, warn};
-use serene_data::build::BuildReason;
+use serde::{Deserialize, Serialize};
+use serene_data::build::{BuildProgress, BuildQueue, BuildQueueTarget, BuildReason, BuildState};
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -18,6 +21,7 @@ use tokio::sync::{mpsc, Mutex};
 
 /// metadata associated with a build
 /// can be used to override stuff like clean
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BuildMeta {
     /// reason the build started
     pub reason: BuildReason,
@@ -27,15 +31,20 @@ pub struct BuildMeta {
     pub clean: bool,
     /// don't check if a package can be updated
     pub force: bool,
+    /// commit which was pushed to trigger the build
+    pub commit: Option<String>,
 }
 
 impl BuildMeta {
     pub fn new(reason: BuildReason, resolve: bool, clean: bool, force: bool) -> Self {
-        Self { resolve, reason, clean, force }
+        Self { resolve, reason, clean, force, commit: None }
     }
     pub fn normal(reason: BuildReason) -> Self {
         Self::new(reason, true, false, false)
     }
+    pub fn pushed(commit: String) -> Self {
+        Self { commit: Some(commit), ..Self::normal(BuildReason::Webhook) }
+    }
 }
 
 /// this struct schedules builds for all packages
@@ -48,6 +57,7 @@ pub struct BuildScheduler {
     signal: Option<Sender<()>>,
     jobs: Arc<Mutex<HashMap<DateTime<Utc>, HashSet<String>>>>,
     lock: Arc<Mutex<HashSet<String>>>,
+    sessions: SessionRegistryInstance,
 }
 
 impl BuildScheduler {
@@ -66,6 +76,7 @@ impl BuildScheduler {
             signal: None,
             jobs: Arc::new(Mutex::new(HashMap::new())),
             lock: Arc::new(Mutex::new(HashSet::new())),
+            sessions: Arc::new(Mutex::new(Default::default())),
         }
     }
 
@@ -93,14 +104,78 @@ impl BuildScheduler {
         let db = self.db.clone();
         let broadcast = self.broadcast.clone();
         let srcinfo_generator = self.srcinfo_generator.clone();
+        let sessions = self.sessions.clone();
 
         tokio::spawn(async move {
-            Self::run_now(packages, builder, lock, db, broadcast, srcinfo_generator, meta).await
+            Self::run_now(packages, builder, lock, db, broadcast, srcinfo_generator, sessions, meta)
+                .await
         });
 
         Ok(())
     }
 
+    /// runs the builds which were interrupted by a restart again, builds which
+    /// were started together are run in the same session again
+    pub async fn requeue(&self, builds: Vec<BuildSummary>) -> anyhow::Result<()> {
+        let mut sessions: HashMap<BuildMeta, Vec<Package>> = HashMap::new();
+
+        for build in builds {
+            let Some(package) = Package::find(&build.package, &self.db).await? else {
+                debug!("not requeueing build for {}, package was removed", build.package);
+                continue;
+            };
+
+            let meta = build.meta.unwrap_or_else(|| BuildMeta::normal(build.reason));
+            sessions.entry(meta).or_default().push(package);
+        }
+
+        for (meta, packages) in sessions {
+            info!(
+                "requeueing interrupted builds for {}",
+                packages.iter().map(|p| p.base.clone()).collect::<Vec<_>>().join(", ")
+            );
+
+            self.run(packages, meta).await?;
+        }
+
+        Ok(())
+    }
+
+    /// checks whether a package is currently part of a running build session
+    pub async fn is_locked(&self, package: &Package) -> bool {
+        self.lock.lock().await.contains(&package.base)
+    }
+
+    /// collects the current contents of the build queue
+    pub async fn queue(&self) -> BuildQueue {
+        let mut locked = self.lock.lock().await.iter().cloned().collect::<Vec<_>>();
+        locked.sort();
+
+        let mut scheduled = self
+            .jobs
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, packages)| !packages.is_empty())
+            .map(|(time, packages)| {
+                let mut packages = packages.iter().cloned().collect::<Vec<_>>();
+                packages.sort();
+
+                BuildQueueTarget { time: *time, packages }
+            })
+            .collect::<Vec<_>>();
+        scheduled.sort_by_key(|t| t.time);
+
+        BuildQueue {
+            slots: CONFIG.concurrent_builds,
+            running: self.builder.running(),
+            waiting: self.builder.queued().await,
+            locked,
+            sessions: self.sessions.lock().await.sessions(),
+            scheduled,
+        }
+    }
+
     /// schedules the builds for a package if it is enabled
     pub async fn schedule(&mut self, package: &Package) -> anyhow::Result<()> {
         self.unschedule(package).await?;
@@ -143,6 +218,7 @@ impl BuildScheduler {
         let srcinfo_generator = self.srcinfo_generator.clone();
         let builder = self.builder.clone();
         let lock = self.lock.clone();
+        let sessions = self.sessions.clone();
 
         tokio::spawn(async move {
             loop {
@@ -193,6 +269,7 @@ impl BuildScheduler {
                     let db = db.clone();
                     let broadcast = broadcast.clone();
                     let srcinfo_generator = srcinfo_generator.clone();
+                    let sessions = sessions.clone();
 
                     tokio::spawn(async move {
                         Self::run_now(
@@ -202,6 +279,7 @@ impl BuildScheduler {
                             db,
                             broadcast,
                             srcinfo_generator,
+                            sessions,
                             BuildMeta::normal(BuildReason::Schedule),
                         )
                         .await
@@ -258,6 +336,7 @@ impl BuildScheduler {
         db: Database,
         broadcast: BroadcastInstance,
         srcinfo_generator: SrcinfoGeneratorInstance,
+        sessions: SessionRegistryInstance,
         meta: BuildMeta,
     ) {
         info!(
@@ -298,6 +377,49 @@ impl BuildScheduler {
             }
         }
 
+        // packages whose upstream maintainer changed are no longer trusted, so they are
+        // disabled until the change is acknowledged
+        let mut trusted = vec![];
+        for mut package in packages {
+            let Some(change) = package.source.get_maintainer_change() else {
+                trusted.push(package);
+                continue;
+            };
+
+            let disable = package.enabled;
+            if disable {
+                warn!("disabling {} as the {} on the aur", package.base, change.describe());
+
+                package.enabled = false;
+                if let Err(e) = package.change_settings(&db).await {
+                    error!("failed to disable untrusted package {}: {e:#}", package.base);
+                }
+            }
+
+            if disable || meta.reason == BuildReason::Manual {
+                let state = BuildState::Cancelled(format!(
+                    "{} on the aur, acknowledge it with `serene info {} trust`",
+                    change.describe(),
+                    package.base
+                ));
+
+                let mut summary = BuildSummary::start(&package, meta.clone());
+                summary.end(state.clone());
+
+                if let Err(e) = summary.save(&db).await {
+                    error!("failed to save cancelled build for {}: {e:#}", package.base);
+                }
+
+                broadcast.change(&package.base, state).await;
+            } else {
+                debug!("skipping build for {}, maintainer change is unacknowledged", package.base);
+            }
+
+            lock.lock().await.remove(&package.base);
+            builder.take_stopping(&package.base).await;
+        }
+        packages = trusted;
+
         // remove packages which are already built (and unlock them)
         if !meta.force {
             let mut locked = lock.lock().await;
@@ -305,16 +427,64 @@ impl BuildScheduler {
             for p in packages.extract_if(.., |p| p.newest_built()) {
                 debug!("skipping build for {}, is up-to-date", p.base);
                 locked.remove(&p.base);
+                builder.take_stopping(&p.base).await;
             }
         }
 
+        // hold back packages whose build files changed until they are reviewed
+        let mut reviewed = vec![];
+        for mut package in packages {
+            if !package.review {
+                reviewed.push(package);
+                continue;
+            }
+
+            let state = match review::check(&mut package, &meta, &db).await {
+                Ok(ReviewOutcome::Proceed) => {
+                    reviewed.push(package);
+                    continue;
+                }
+                Ok(ReviewOutcome::Held) => Some(BuildState::Review),
+                Ok(ReviewOutcome::Waiting) => {
+                    debug!("skipping build for {}, changes are awaiting review", package.base);
+                    None
+                }
+                Ok(ReviewOutcome::Rejected) => {
+                    debug!("skipping build for {}, changes were rejected", package.base);
+                    None
+                }
+                Err(e) => Some(BuildState::Fatal(
+                    format!("failed to check build files for review: {e:#}"),
+                    BuildProgress::Update,
+                )),
+            };
+
+            if let Some(state) = state {
+                let mut summary = BuildSummary::start(&package, meta.clone());
+                summary.end(state.clone());
+
+                if let Err(e) = summary.save(&db).await {
+                    error!("failed to save held build for {}: {e:#}", package.base);
+                }
+
+                broadcast.change(&package.base, state).await;
+                notify::build_finished(&db, &package, &summary).await;
+            }
+
+            lock.lock().await.remove(&package.base);
+            builder.take_stopping(&package.base).await;
+        }
+        packages = reviewed;
+
         let targets = packages.iter().map(|p| p.base.clone()).collect::<HashSet<_>>();
 
-        match BuildSession::start(packages, &db, builder, broadcast, meta).await {
+        match BuildSession::start(packages, &db, builder.clone(), broadcast, sessions, meta).await {
             Ok(mut session) => {
                 if let Err(e) = session.run().await {
                     error!("failed to run build session: {e:#}");
                 }
+
+                session.unregister().await;
             }
             Err(e) => {
                 error!("failed to start build session: {e:#}");
@@ -327,6 +497,8 @@ impl BuildScheduler {
 
             for package in targets {
                 locked.remove(&package);
+                // stop requests which came too late are no longer relevant
+                builder.take_stopping(&package).await;
             }
         }
     }
@@ -6,18 +6,42 @@ use crate::package::Package;
 use crate::resolve::build::BuildResolver;
 use crate::web::broadcast::{Broadcast, BroadcastInstance};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use log::{debug, error, info, warn};
-use serene_data::build::{BuildProgress, BuildReason, BuildState};
-use std::collections::HashSet;
+use serene_data::build::{BuildProgress, BuildQueueSession, BuildState};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use tokio::select;
 use tokio::sync::mpsc::Sender;
-use tokio::sync::oneshot;
+use tokio::sync::{Mutex, oneshot};
+
+/// keeps the current state of all running build sessions
+#[derive(Default)]
+pub struct SessionRegistry {
+    next: usize,
+    sessions: HashMap<usize, BuildQueueSession>,
+}
+
+impl SessionRegistry {
+    /// returns the states of all running sessions, oldest first
+    pub fn sessions(&self) -> Vec<BuildQueueSession> {
+        let mut sessions = self.sessions.values().cloned().collect::<Vec<_>>();
+        sessions.sort_by_key(|s| s.started);
+        sessions
+    }
+}
+
+pub type SessionRegistryInstance = Arc<Mutex<SessionRegistry>>;
 
 pub struct BuildSession<'a> {
     packages: Vec<(Package, BuildSummary, HashSet<String>)>,
     building: HashSet<String>,
     meta: BuildMeta,
 
+    id: usize,
+    started: DateTime<Utc>,
+    registry: SessionRegistryInstance,
+
     builder: BuilderInstance,
     broadcast: BroadcastInstance,
     db: &'a Database,
@@ -35,15 +59,16 @@ impl<'a> BuildSession<'a> {
         db: &'a Database,
         builder: BuilderInstance,
         broadcast: BroadcastInstance,
+        registry: SessionRegistryInstance,
         meta: BuildMeta,
     ) -> Result<Self> {
         let result = if meta.resolve && CONFIG.resolve_build_sequence && packages.len() > 1 {
-            Self::resolve(packages, meta.reason, db, broadcast.clone()).await?
+            Self::resolve(packages, meta.clone(), db, broadcast.clone()).await?
         } else {
             let mut result = vec![];
 
             for package in packages {
-                let summary = BuildSummary::start(&package, meta.reason);
+                let summary = BuildSummary::start(&package, meta.clone());
                 summary.save(db).await?;
                 broadcast.change(&package.base, summary.state.clone()).await;
 
@@ -53,7 +78,58 @@ impl<'a> BuildSession<'a> {
             result
         };
 
-        Ok(Self { builder, broadcast, db, packages: result, building: HashSet::new(), meta })
+        let id = {
+            let mut registry = registry.lock().await;
+            registry.next += 1;
+            registry.next
+        };
+
+        let session = Self {
+            builder,
+            broadcast,
+            db,
+            packages: result,
+            building: HashSet::new(),
+            meta,
+            id,
+            started: Utc::now(),
+            registry,
+        };
+
+        session.publish().await;
+        Ok(session)
+    }
+
+    /// publishes the current state of the session to the registry
+    async fn publish(&self) {
+        let mut building = self.building.iter().cloned().collect::<Vec<_>>();
+        building.sort();
+
+        let mut pending = self
+            .packages
+            .iter()
+            .map(|(package, _, deps)| {
+                let mut deps = deps.iter().cloned().collect::<Vec<_>>();
+                deps.sort();
+
+                (package.base.clone(), deps)
+            })
+            .collect::<Vec<_>>();
+        pending.sort();
+
+        let state = BuildQueueSession {
+            reason: self.meta.reason,
+            started: self.started,
+            building,
+            pending,
+        };
+
+        self.registry.lock().await.sessions.insert(self.id, state);
+    }
+
+    /// removes the session from the registry, should be called once it is over
+    pub async fn unregister(&self) {
+        self.registry.lock().await.sessions.remove(&self.id);
     }
 
     // FIXME: remove this once Alpm is sync, see sync.rs
@@ -62,7 +138,7 @@ impl<'a> BuildSession<'a> {
     /// (cause alpm is non-send)
     async fn resolve(
         packages: Vec<Package>,
-        reason: BuildReason,
+        meta: BuildMeta,
         db: &'a Database,
         broadcast: Arc<Broadcast>,
     ) -> Result<Vec<(Package, BuildSummary, HashSet<String>)>> {
@@ -86,7 +162,7 @@ impl<'a> BuildSession<'a> {
                             Err(e) => break 'content Err(e),
                         };
 
-                        match resolver.add_and_resolve(packages, reason).await {
+                        match resolver.add_and_resolve(packages, meta).await {
                             Ok(r) => Ok(r),
                             Err(e) => {
                                 if let Err(again) = resolver.finish_fatally(&e.to_string()).await {
@@ -111,30 +187,35 @@ impl<'a> BuildSession<'a> {
     /// builds all packages in the optimal sequence
     pub async fn run(&mut self) -> Result<()> {
         let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let mut stops = self.builder.subscribe_stopping();
 
         loop {
-            // amount of packages we are currently allowed to start
-            let free = CONFIG.concurrent_builds - self.building.len();
+            // stop packages which have not yet started
+            self.stop_pending().await?;
 
-            // run ready packages
-            let buildable = self
-                .packages
-                .extract_if(.., |(_, _, d)| d.is_empty())
-                .take(free)
-                .collect::<Vec<_>>();
+            // run ready packages, they wait for a free build slot themselves
+            let buildable =
+                self.packages.extract_if(.., |(_, _, d)| d.is_empty()).collect::<Vec<_>>();
 
             for (package, summary, _) in buildable {
                 self.build_package(package, summary, tx.clone()).await?;
             }
 
+            self.publish().await;
+
             // check if empty
             if self.building.is_empty() {
                 info!("finished building successfully");
                 break;
             }
 
-            // wait for next
-            let Some(BuildResult(built, success)) = rx.recv().await else {
+            // wait for next, or for a package to be stopped
+            let result = select! {
+                result = rx.recv() => result,
+                _ = stops.recv() => continue,
+            };
+
+            let Some(BuildResult(built, success)) = result else {
                 warn!("didn't catch previous ending condition!");
                 break;
             };
@@ -142,22 +223,13 @@ impl<'a> BuildSession<'a> {
             info!("received build result for package {built} with status {success}");
             self.building.remove(&built);
 
-            // updating waiting packages
-            if success || CONFIG.resolve_ignore_failed {
-                for (_, _, deps) in &mut self.packages {
-                    deps.remove(&built);
-                }
+            let message = if self.builder.take_stopping(&built).await {
+                format!("build of dependency {built} was stopped")
             } else {
-                for (pkg, mut sum, _) in
-                    self.packages.extract_if(.., |(_, _, d)| d.contains(&built))
-                {
-                    sum.end(BuildState::Cancelled(format!(
-                        "failed to build dependency {built} successfully"
-                    )));
-                    sum.change(self.db).await?;
-                    self.broadcast.change(&pkg.base, sum.state.clone()).await;
-                }
-            }
+                format!("failed to build dependency {built} successfully")
+            };
+
+            self.release_dependents(&built, success, message).await?;
         }
 
         for (p, summary, rem) in &mut self.packages {
@@ -177,6 +249,59 @@ impl<'a> BuildSession<'a> {
         Ok(())
     }
 
+    /// stops the pending packages which were requested to be stopped
+    async fn stop_pending(&mut self) -> Result<()> {
+        let mut stopped = vec![];
+        for (package, _, _) in &self.packages {
+            if self.builder.take_stopping(&package.base).await {
+                stopped.push(package.base.clone());
+            }
+        }
+
+        for (pkg, mut sum, _) in self.packages.extract_if(.., |(p, _, _)| stopped.contains(&p.base))
+        {
+            info!("stopped pending build for package {}", pkg.base);
+
+            sum.end(BuildState::Stopped(BuildProgress::Resolve));
+            sum.change(self.db).await?;
+            self.broadcast.change(&pkg.base, sum.state.clone()).await;
+        }
+
+        for base in stopped {
+            self.release_dependents(
+                &base,
+                false,
+                format!("build of dependency {base} was stopped"),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// releases the packages waiting on a finished package, or cancels them with
+    /// the given message if it was not successful
+    async fn release_dependents(
+        &mut self,
+        built: &str,
+        success: bool,
+        message: String,
+    ) -> Result<()> {
+        if success || CONFIG.resolve_ignore_failed {
+            for (_, _, deps) in &mut self.packages {
+                deps.remove(built);
+            }
+        } else {
+            for (pkg, mut sum, _) in self.packages.extract_if(.., |(_, _, d)| d.contains(built)) {
+                sum.end(BuildState::Cancelled(message.clone()));
+                sum.change(self.db).await?;
+                self.broadcast.change(&pkg.base, sum.state.clone()).await;
+            }
+        }
+
+        Ok(())
+    }
+
     /// builds a package in a separate thread / routine
     async fn build_package(
         &mut self,
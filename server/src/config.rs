@@ -1,6 +1,9 @@
 use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
 use log::warn;
+use serene_data::build::{LintRule, LintSeverity};
+use serene_data::package::NotifyRule;
+use std::collections::HashMap;
 use std::env;
 use std::str::FromStr;
 
@@ -78,7 +81,7 @@ pub struct Config {
     pub resolve_build_sequence: bool,
     /// still build depending packages even if dependency failed
     pub resolve_ignore_failed: bool,
-    /// maximal amount of concurrent builds allowed PER SESSION
+    /// maximal amount of concurrent builds allowed on the whole server
     pub concurrent_builds: usize,
     /// forces clean on all packages, removing container after build
     pub force_clean: bool,
@@ -86,6 +89,34 @@ pub struct Config {
     pub edge_cli: bool,
     /// optional name of packager set in package metadata
     pub packager: Option<String>,
+    /// default time in minutes after which a build is killed
+    pub build_timeout: Option<usize>,
+    /// default memory limit of build containers in mebibytes
+    pub build_memory_limit: Option<usize>,
+    /// default amount of cpus a build container can use
+    pub build_cpu_limit: Option<f32>,
+    /// default maximal amount of processes in a build container
+    pub build_pids_limit: Option<usize>,
+    /// build packages again whose builds were interrupted by a restart
+    pub requeue_unfinished: bool,
+    /// retry failed builds once in a clean container if they were not clean
+    pub retry_clean: bool,
+    /// maximal age of builds in days before they are purged
+    pub retention_max_age: Option<usize>,
+    /// maximal amount of builds kept per package
+    pub retention_max_builds: Option<usize>,
+    /// schedule for purging old builds
+    pub schedule_retention: String,
+    /// url to which notifications are posted as json
+    pub notify_url: Option<String>,
+    /// shell command which is run for notifications
+    pub notify_command: Option<String>,
+    /// default rule on which build results notifications are sent
+    pub notify_rule: NotifyRule,
+    /// token which allows access to the metrics without a secret
+    pub metrics_token: Option<String>,
+    /// severities of the lint rules which differ from warn
+    pub lint_severity: HashMap<LintRule, LintSeverity>,
 }
 
 impl Default for Config {
@@ -132,6 +163,26 @@ impl Default for Config {
             sync_mirror: "https://mirror.init7.net/archlinux/{repo}/os/{arch}".to_string(),
 
             packager: None,
+
+            build_timeout: None,
+            build_memory_limit: None,
+            build_cpu_limit: None,
+            build_pids_limit: None,
+
+            requeue_unfinished: false,
+            retry_clean: false,
+
+            retention_max_age: None,
+            retention_max_builds: None,
+            schedule_retention: "0 0 0 * * *".to_string(),
+
+            notify_url: None,
+            notify_command: None,
+            notify_rule: NotifyRule::Failure,
+
+            metrics_token: None,
+
+            lint_severity: HashMap::new(),
         }
     }
 }
@@ -167,6 +218,73 @@ impl Config {
             .unwrap_or(default)
     }
 
+    fn env_usize_option(name: &str, default: Option<usize>) -> Option<usize> {
+        env::var(name)
+            .ok()
+            .and_then(|s| {
+                usize::from_str(&s)
+                    .map_err(|_| {
+                        warn!("failed to parse {name} as usize, using default {default:?}")
+                    })
+                    .ok()
+            })
+            .or(default)
+    }
+
+    fn env_f32_option(name: &str, default: Option<f32>) -> Option<f32> {
+        env::var(name)
+            .ok()
+            .and_then(|s| {
+                f32::from_str(&s)
+                    .map_err(|_| warn!("failed to parse {name} as f32, using default {default:?}"))
+                    .ok()
+            })
+            .or(default)
+    }
+
+    fn env_notify_rule(name: &str, default: NotifyRule) -> NotifyRule {
+        env::var(name)
+            .ok()
+            .and_then(|s| {
+                NotifyRule::from_str(&s)
+                    .map_err(|_| {
+                        warn!("failed to parse {name} as notify rule, using default {default}")
+                    })
+                    .ok()
+            })
+            .unwrap_or(default)
+    }
+
+    fn env_lint_severity(
+        name: &str,
+        default: HashMap<LintRule, LintSeverity>,
+    ) -> HashMap<LintRule, LintSeverity> {
+        let Ok(value) = env::var(name) else {
+            return default;
+        };
+
+        // formatted like `pipe-to-shell=block,new-domain=off`
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| {
+                let parsed = s.split_once('=').and_then(|(rule, severity)| {
+                    Some((
+                        LintRule::from_str(rule.trim()).ok()?,
+                        LintSeverity::from_str(severity.trim()).ok()?,
+                    ))
+                });
+
+                if parsed.is_none() {
+                    warn!("failed to parse `{s}` in {name} as lint severity, ignoring it");
+                }
+
+                parsed
+            })
+            .collect()
+    }
+
     fn env_bool(name: &str, default: bool) -> bool {
         env::var(name)
             .ok()
@@ -223,7 +341,37 @@ impl Config {
 
             sync_mirror: Self::env_string("SYNC_MIRROR", default.sync_mirror),
 
-            packager: Self::env_string_option("PACKAGER", default.packager)
+            packager: Self::env_string_option("PACKAGER", default.packager),
+
+            build_timeout: Self::env_usize_option("BUILD_TIMEOUT", default.build_timeout),
+            build_memory_limit: Self::env_usize_option("BUILD_MEMORY_LIMIT", default.build_memory_limit),
+            build_cpu_limit: Self::env_f32_option("BUILD_CPU_LIMIT", default.build_cpu_limit),
+            build_pids_limit: Self::env_usize_option("BUILD_PIDS_LIMIT", default.build_pids_limit),
+
+            requeue_unfinished: Self::env_bool("REQUEUE_UNFINISHED", default.requeue_unfinished),
+            retry_clean: Self::env_bool("RETRY_CLEAN", default.retry_clean),
+
+            retention_max_age: Self::env_usize_option("RETENTION_MAX_AGE", default.retention_max_age),
+            retention_max_builds: Self::env_usize_option("RETENTION_MAX_BUILDS", default.retention_max_builds),
+            schedule_retention: Self::env_string("SCHEDULE_RETENTION", default.schedule_retention),
+
+            notify_url: Self::env_string_option("NOTIFY_URL", default.notify_url),
+            notify_command: Self::env_string_option("NOTIFY_COMMAND", default.notify_command),
+            notify_rule: Self::env_notify_rule("NOTIFY_RULE", default.notify_rule),
+
+            metrics_token: Self::env_string_option("METRICS_TOKEN", default.metrics_token),
+
+            lint_severity: Self::env_lint_severity("LINT_SEVERITY", default.lint_severity),
         }
     }
+
+    /// returns the severity of a lint rule, rules are warnings by default
+    pub fn get_lint_severity(&self, rule: LintRule) -> LintSeverity {
+        self.lint_severity.get(&rule).copied().unwrap_or(LintSeverity::Warn)
+    }
+
+    /// returns whether any lint rule blocks builds
+    pub fn lint_blocks(&self) -> bool {
+        self.lint_severity.values().any(|s| *s == LintSeverity::Block)
+    }
 }
@@ -0,0 +1,102 @@
+use crate::database::{Database, DatabaseConversion};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serene_data::AuditInfo;
+use sqlx::{query, query_as};
+
+/// See migrations:
+/// server/migrations/20261016222046_audit.sql
+#[derive(Debug)]
+struct AuditRecord {
+    id: i64,
+    time: NaiveDateTime,
+    secret: String,
+    endpoint: String,
+    body: Option<String>,
+}
+
+/// action which was performed through the api with a write secret
+pub struct AuditEntry {
+    /// when the action was performed
+    pub time: DateTime<Utc>,
+    /// comment of the secret which performed it
+    pub secret: String,
+    /// method and path of the request
+    pub endpoint: String,
+    /// json body of the request, with private values redacted
+    pub body: Option<String>,
+}
+
+impl DatabaseConversion<AuditRecord> for AuditEntry {
+    fn create_record(&self) -> Result<AuditRecord> {
+        Ok(AuditRecord {
+            id: 0,
+            time: self.time.naive_utc(),
+            secret: self.secret.clone(),
+            endpoint: self.endpoint.clone(),
+            body: self.body.clone(),
+        })
+    }
+
+    fn from_record(other: AuditRecord) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            time: other.time.and_utc(),
+            secret: other.secret,
+            endpoint: other.endpoint,
+            body: other.body,
+        })
+    }
+}
+
+impl AuditEntry {
+    pub fn new(secret: String, endpoint: String, body: Option<String>) -> Self {
+        Self { time: Utc::now(), secret, endpoint, body }
+    }
+
+    /// Find the latest entries of the audit log, newest first
+    pub async fn find_latest_n(n: u32, db: &Database) -> Result<Vec<Self>> {
+        let records = query_as!(
+            AuditRecord,
+            r#"
+            SELECT * FROM audit ORDER BY time DESC, id DESC LIMIT $1
+        "#,
+            n
+        )
+        .fetch_all(db)
+        .await?;
+
+        records.into_iter().map(AuditEntry::from_record).collect()
+    }
+
+    /// Saves the entry to the audit log
+    pub async fn save(&self, db: &Database) -> Result<()> {
+        let record = self.create_record()?;
+
+        query!(
+            r#"
+            INSERT INTO audit (time, secret, endpoint, body)
+            VALUES ($1, $2, $3, $4)
+        "#,
+            record.time,
+            record.secret,
+            record.endpoint,
+            record.body
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn to_info(&self) -> AuditInfo {
+        AuditInfo {
+            time: self.time,
+            secret: self.secret.clone(),
+            endpoint: self.endpoint.clone(),
+            body: self.body.clone(),
+        }
+    }
+}
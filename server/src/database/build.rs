@@ -1,11 +1,15 @@
 use crate::build::BuildSummary;
+use crate::build::schedule::BuildMeta;
 use crate::database::{Database, DatabaseConversion};
+use crate::package::files::BuildFiles;
 use crate::runner::RunStatus;
 use crate::runner::stats::CgroupStats;
 use anyhow::{Context, Result, anyhow};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use log::{debug, info, trace};
-use serene_data::build::{BuildProgress, BuildReason, BuildState};
+use serene_data::build::{
+    BuildFailure, BuildProgress, BuildReason, BuildState, FailureCategory, LintFinding,
+};
 use sqlx::{query, query_as};
 use std::str::FromStr;
 
@@ -15,10 +19,19 @@ const STATE_RUNNING: &str = "running";
 const STATE_SUCCESS: &str = "success";
 const STATE_FAILURE: &str = "failure";
 const STATE_FATAL: &str = "fatal";
+const STATE_STOPPED: &str = "stopped";
+const STATE_REVIEW: &str = "review";
 
 /// See migrations:
 /// server/migrations/20240210164401_build.sql
 /// server/migrations/20240917122808_build_reason.sql
+/// server/migrations/20260207012716_cgroup_stats.sql
+/// server/migrations/20261016141027_limits.sql
+/// server/migrations/20261016170342_build_meta.sql
+/// server/migrations/20261016190214_failure.sql
+/// server/migrations/20261016224412_commit.sql
+/// server/migrations/20261017002318_build_files.sql
+/// server/migrations/20261017014502_lint.sql
 #[derive(Debug)]
 struct BuildRecord {
     package: String,
@@ -44,6 +57,20 @@ struct BuildRecord {
     cpu_system: Option<i64>,
     io_tbr: Option<i64>,
     io_tbw: Option<i64>,
+    oom_kills: Option<i64>,
+
+    resolve: Option<bool>,
+    clean: Option<bool>,
+    force: Option<bool>,
+
+    failure: Option<String>,
+    failure_hint: Option<String>,
+
+    commit_hash: Option<String>,
+
+    files: Option<String>,
+
+    lint: Option<String>,
 }
 
 impl DatabaseConversion<BuildRecord> for BuildSummary {
@@ -57,6 +84,8 @@ impl DatabaseConversion<BuildRecord> for BuildSummary {
             BuildState::Fatal(m, p) => {
                 (STATE_FATAL.to_owned(), Some(p.to_string()), Some(m.clone()))
             }
+            BuildState::Stopped(p) => (STATE_STOPPED.to_owned(), Some(p.to_string()), None),
+            BuildState::Review => (STATE_REVIEW.to_owned(), None, None),
         };
 
         Ok(BuildRecord {
@@ -79,6 +108,24 @@ impl DatabaseConversion<BuildRecord> for BuildSummary {
             cpu_system: self.stats.as_ref().and_then(|s| s.cpu_system.map(|i| i as i64)),
             io_tbr: self.stats.as_ref().and_then(|s| s.io_tbr.map(|i| i as i64)),
             io_tbw: self.stats.as_ref().and_then(|s| s.io_tbw.map(|i| i as i64)),
+            oom_kills: self.stats.as_ref().and_then(|s| s.oom_kills.map(|i| i as i64)),
+
+            resolve: self.meta.as_ref().map(|m| m.resolve),
+            clean: self.meta.as_ref().map(|m| m.clean),
+            force: self.meta.as_ref().map(|m| m.force),
+
+            failure: self.failure.as_ref().map(|f| f.category.to_string()),
+            failure_hint: self.failure.as_ref().map(|f| f.hint.clone()),
+
+            commit_hash: self.meta.as_ref().and_then(|m| m.commit.clone()),
+
+            // the build files are only loaded when needed, see change_files
+            files: None,
+
+            lint: (!self.findings.is_empty())
+                .then(|| serde_json::to_string(&self.findings))
+                .transpose()
+                .context("failed to serialize lint findings")?,
         })
     }
 
@@ -90,6 +137,7 @@ impl DatabaseConversion<BuildRecord> for BuildSummary {
             (STATE_SUCCESS, None, None) => BuildState::Success,
             (STATE_FAILURE, None, None) => BuildState::Failure,
             (STATE_PENDING, None, None) => BuildState::Pending,
+            (STATE_REVIEW, None, None) => BuildState::Review,
             (STATE_CANCELLED, None, Some(m)) => BuildState::Cancelled(m),
             (STATE_RUNNING, Some(p), None) => BuildState::Running(
                 BuildProgress::from_str(&p).map_err(|_| anyhow!("no correct progress"))?,
@@ -98,6 +146,9 @@ impl DatabaseConversion<BuildRecord> for BuildSummary {
                 m,
                 BuildProgress::from_str(&p).map_err(|_| anyhow!("no correct progress"))?,
             ),
+            (STATE_STOPPED, Some(p), None) => BuildState::Stopped(
+                BuildProgress::from_str(&p).map_err(|_| anyhow!("no correct progress"))?,
+            ),
             _ => return Err(anyhow!("no valid state representation found")),
         };
 
@@ -107,22 +158,47 @@ impl DatabaseConversion<BuildRecord> for BuildSummary {
             cpu_system: other.cpu_system.map(|i| i as usize),
             io_tbr: other.io_tbr.map(|i| i as usize),
             io_tbw: other.io_tbw.map(|i| i as usize),
+            oom_kills: other.oom_kills.map(|i| i as usize),
         };
 
+        let reason = BuildReason::from_str(&other.reason).unwrap_or(BuildReason::Unknown);
+
         Ok(BuildSummary {
             package: other.package,
-            reason: BuildReason::from_str(&other.reason).unwrap_or(BuildReason::Unknown),
+            reason,
+            meta: match (other.resolve, other.clean, other.force) {
+                (Some(resolve), Some(clean), Some(force)) => Some(BuildMeta {
+                    commit: other.commit_hash,
+                    ..BuildMeta::new(reason, resolve, clean, force)
+                }),
+                _ => None,
+            },
             state,
             version: other.version,
             started: other.started.and_utc(),
             ended: other.ended.map(|d| d.and_utc()),
             stats: Some(stats),
+            failure: match (other.failure, other.failure_hint) {
+                (Some(category), Some(hint)) => FailureCategory::from_str(&category)
+                    .ok()
+                    .map(|category| BuildFailure { category, hint }),
+                _ => None,
+            },
             details: match (other.run_success, other.run_started, other.run_ended) {
-                (Some(success), Some(started), Some(ended)) => {
-                    Some(RunStatus { success, started: started.and_utc(), ended: ended.and_utc() })
-                }
+                (Some(success), Some(started), Some(ended)) => Some(RunStatus {
+                    success,
+                    timed_out: false,
+                    started: started.and_utc(),
+                    ended: ended.and_utc(),
+                }),
                 _ => None,
             },
+            findings: other
+                .lint
+                .map(|l| serde_json::from_str::<Vec<LintFinding>>(&l))
+                .transpose()
+                .context("failed to deserialize lint findings")?
+                .unwrap_or_default(),
         })
     }
 }
@@ -236,10 +312,10 @@ impl BuildSummary {
         let record = self.create_record()?;
 
         query!(r#"
-            INSERT INTO build (package, started, ended, state, progress, fatal, version, run_success, run_logs, run_started, run_ended, reason, mem_peak, cpu_system, cpu_user, io_tbr, io_tbw)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            INSERT INTO build (package, started, ended, state, progress, fatal, version, run_success, run_logs, run_started, run_ended, reason, mem_peak, cpu_system, cpu_user, io_tbr, io_tbw, oom_kills, resolve, clean, force, failure, failure_hint, commit_hash, lint)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25)
         "#,
-            record.package, record.started, record.ended, record.state, record.progress, record.fatal, record.version, record.run_success, record.run_logs, record.run_started, record.run_ended, record.reason, record.mem_peak, record.cpu_system, record.cpu_user, record.io_tbr, record.io_tbw
+            record.package, record.started, record.ended, record.state, record.progress, record.fatal, record.version, record.run_success, record.run_logs, record.run_started, record.run_ended, record.reason, record.mem_peak, record.cpu_system, record.cpu_user, record.io_tbr, record.io_tbw, record.oom_kills, record.resolve, record.clean, record.force, record.failure, record.failure_hint, record.commit_hash, record.lint
         )
             .execute(db).await?;
 
@@ -251,16 +327,55 @@ impl BuildSummary {
 
         query!(r#"
             UPDATE build
-            SET ended = $2, state = $3, progress = $4, fatal = $5, version = $6, run_success = $7, run_logs = $8, run_started = $9, run_ended = $10, mem_peak = $11, cpu_system = $12, cpu_user = $13, io_tbr = $14, io_tbw = $15
+            SET ended = $2, state = $3, progress = $4, fatal = $5, version = $6, run_success = $7, run_logs = $8, run_started = $9, run_ended = $10, mem_peak = $11, cpu_system = $12, cpu_user = $13, io_tbr = $14, io_tbw = $15, oom_kills = $16, failure = $17, failure_hint = $18, lint = $19
             WHERE started = $1
         "#,
-            record.started, record.ended, record.state, record.progress, record.fatal, record.version, record.run_success, record.run_logs, record.run_started, record.run_ended, record.mem_peak, record.cpu_system, record.cpu_user, record.io_tbr, record.io_tbw
+            record.started, record.ended, record.state, record.progress, record.fatal, record.version, record.run_success, record.run_logs, record.run_started, record.run_ended, record.mem_peak, record.cpu_system, record.cpu_user, record.io_tbr, record.io_tbw, record.oom_kills, record.failure, record.failure_hint, record.lint
         )
             .execute(db).await?;
 
         Ok(())
     }
 
+    /// Stores the build files the build was run with
+    pub async fn change_files(&self, files: &BuildFiles, db: &Database) -> Result<()> {
+        let started = self.started.naive_utc();
+        let files = serde_json::to_string(files).context("failed to serialize build files")?;
+
+        query!(
+            r#"
+            UPDATE build SET files = $2 WHERE started = $1
+        "#,
+            started,
+            files
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Finds the build files the build was run with, older builds don't have
+    /// them recorded
+    pub async fn find_files(&self, db: &Database) -> Result<Option<BuildFiles>> {
+        let started = self.started.naive_utc();
+
+        let files = query!(
+            r#"
+            SELECT files FROM build WHERE started = $1 AND package = $2
+        "#,
+            started,
+            self.package
+        )
+        .fetch_optional(db)
+        .await?
+        .and_then(|r| r.files);
+
+        files
+            .map(|f| serde_json::from_str(&f).context("failed to deserialize build files"))
+            .transpose()
+    }
+
     pub async fn delete(&self, db: &Database) -> Result<()> {
         let base = self.started.naive_utc();
 
@@ -1,20 +1,75 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 
 use anyhow::{Context, Result};
-use tokio::fs;
+use async_compression::tokio::bufread::ZstdDecoder;
+use async_compression::tokio::write::ZstdEncoder;
+use chrono::{DateTime, Utc};
+use log::{debug, info, trace, warn};
+use serene_data::build::BuildLogSection;
+use tokio::fs::{self, File};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 
 use crate::{build::BuildSummary, package::Package};
 
 const LOG_DIR: &str = "logs";
 
+/// extension of plain log files, as they were stored before compression
+const PLAIN_EXTENSION: &str = "log";
+/// extension of zstd compressed log files
+const COMPRESSED_EXTENSION: &str = "log.zst";
+/// suffix of compressed log files which are still being written
+const PARTIAL_SUFFIX: &str = ".part";
+
+/// prefix of the markers the runner prints before each step of a build
+const SECTION_MARKER: &str = ":: SERENE :: ";
+/// short names of the sections marked by the runner scripts
+const SECTION_NAMES: &[(&str, &str)] = &[
+    ("preparing container", "container"),
+    ("updating system", "update"),
+    ("running per-package preparation commands", "prepare"),
+    ("synchronizing dependencies", "dependencies"),
+    ("starting package build", "build"),
+    ("collecting package information", "info"),
+    ("cleaning up to save space", "cleanup"),
+    ("build script finished", "finish"),
+];
+
+/// reader which yields the decompressed logs of a build
+pub type LogReader = Pin<Box<dyn AsyncRead + Send>>;
+
 /// returns the path to the directory where the logs for a package are stored
 fn path(package: &str) -> PathBuf {
     PathBuf::from(LOG_DIR).join(package)
 }
 
-/// returns the filename of the logs for a build
-fn build_file(build: &BuildSummary) -> String {
-    build.started.naive_utc().format("%Y-%m-%dT%H:%M:%S").to_string() + ".log"
+/// returns the filename of the logs for a build with the given extension
+fn build_file(build: &BuildSummary, extension: &str) -> String {
+    format!("{}.{extension}", build.started.naive_utc().format("%Y-%m-%dT%H:%M:%S"))
+}
+
+/// compresses the given logs into a file, they are written to a temporary file
+/// first so readers never see an incomplete one
+async fn compress(file: &Path, logs: &[u8]) -> Result<()> {
+    let mut partial = file.as_os_str().to_owned();
+    partial.push(PARTIAL_SUFFIX);
+    let partial = PathBuf::from(partial);
+
+    let result = async {
+        let mut encoder = ZstdEncoder::new(File::create(&partial).await?);
+        encoder.write_all(logs).await?;
+        encoder.shutdown().await?;
+
+        fs::rename(&partial, file).await
+    }
+    .await;
+
+    if result.is_err() {
+        // don't leave a broken file behind
+        let _ = fs::remove_file(&partial).await;
+    }
+
+    result.context("failed to write compressed log file")
 }
 
 /// writes the logs for a build to the filesystem
@@ -25,20 +80,146 @@ pub async fn write(build: &BuildSummary, logs: String) -> Result<()> {
         fs::create_dir_all(&path).await.context("failed to create directory to store logs in")?;
     }
 
-    fs::write(path.join(build_file(build)), logs.as_bytes())
+    compress(&path.join(build_file(build, COMPRESSED_EXTENSION)), logs.as_bytes())
         .await
         .context("failed to write logs to file")
 }
 
+/// opens the logs for a build from the filesystem, decompressing them on the
+/// fly if needed
+pub async fn open(build: &BuildSummary) -> Result<Option<LogReader>> {
+    let path = path(&build.package);
+
+    let compressed = path.join(build_file(build, COMPRESSED_EXTENSION));
+    if compressed.is_file() {
+        let file = File::open(compressed).await.context("failed to open log file")?;
+        return Ok(Some(Box::pin(ZstdDecoder::new(BufReader::new(file)))));
+    }
+
+    // logs might not have been migrated yet
+    let plain = path.join(build_file(build, PLAIN_EXTENSION));
+    if plain.is_file() {
+        let file = File::open(plain).await.context("failed to open log file")?;
+        return Ok(Some(Box::pin(file)));
+    }
+
+    Ok(None)
+}
+
 /// reads the logs for a build from the filesystem
 pub async fn read(build: &BuildSummary) -> Result<Option<String>> {
-    let path = path(&build.package).join(build_file(build));
+    let Some(mut reader) = open(build).await? else {
+        return Ok(None);
+    };
+
+    let mut logs = String::new();
+    reader.read_to_string(&mut logs).await.context("failed to read log file")?;
 
-    if path.exists() && path.is_file() {
-        Ok(Some(fs::read_to_string(path).await.context("failed to read log file")?))
+    Ok(Some(logs))
+}
+
+/// parses a section marker line, returning its time and message
+/// older runners did not include the time in the marker
+fn parse_marker(line: &str) -> Option<(Option<DateTime<Utc>>, &str)> {
+    let marker = line.trim_end().strip_prefix(SECTION_MARKER)?;
+
+    if let Some((time, message)) = marker.split_once(" :: ")
+        && let Ok(time) = DateTime::parse_from_rfc3339(time)
+    {
+        Some((Some(time.to_utc()), message))
     } else {
-        Ok(None)
+        Some((None, marker))
+    }
+}
+
+/// returns the short name of a section based on the message of its marker
+fn section_name(message: &str) -> String {
+    if let Some((_, name)) = SECTION_NAMES.iter().find(|(m, _)| *m == message) {
+        return name.to_string();
     }
+
+    // unknown markers are named after their message
+    message
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// splits the logs of a build into the sections marked by the runner, output
+/// before the first marker is put into a section named `init` if there is any
+pub fn sections(build: &BuildSummary, logs: &str) -> Vec<(BuildLogSection, String)> {
+    let mut sections = vec![];
+
+    let mut current = BuildLogSection {
+        name: "init".to_string(),
+        message: "output before the first step".to_string(),
+        started: None,
+        duration: None,
+        lines: 0,
+    };
+    let mut content = String::new();
+
+    for line in logs.split_inclusive('\n') {
+        let Some((started, message)) = parse_marker(line) else {
+            content.push_str(line);
+            continue;
+        };
+
+        if current.name != "init" || !content.trim().is_empty() {
+            sections.push((current, content));
+        }
+
+        current = BuildLogSection {
+            name: section_name(message),
+            message: message.to_string(),
+            started,
+            duration: None,
+            lines: 0,
+        };
+        content = String::new();
+    }
+
+    if current.name != "init" || !content.trim().is_empty() {
+        sections.push((current, content));
+    }
+
+    // a section lasts until the next one starts, or the build ends
+    let ends =
+        sections.iter().skip(1).map(|(s, _)| s.started).chain([build.ended]).collect::<Vec<_>>();
+
+    for ((section, content), end) in sections.iter_mut().zip(ends) {
+        // markers are followed by an empty line
+        let trimmed = content.trim_matches('\n');
+        *content = if trimmed.is_empty() { String::new() } else { format!("{trimmed}\n") };
+
+        section.lines = content.lines().count();
+        section.duration = section.started.zip(end).map(|(start, end)| (end - start).num_seconds());
+    }
+
+    sections
+}
+
+/// removes the logs for a build from the filesystem, returns the amount of
+/// bytes freed
+pub async fn remove(build: &BuildSummary) -> Result<u64> {
+    let path = path(&build.package);
+    let mut freed = 0;
+
+    let compressed = build_file(build, COMPRESSED_EXTENSION);
+    let partial = format!("{compressed}{PARTIAL_SUFFIX}");
+
+    for name in [compressed, partial, build_file(build, PLAIN_EXTENSION)] {
+        let file = path.join(name);
+
+        if file.is_file() {
+            freed += fs::metadata(&file).await.context("failed to read log file metadata")?.len();
+            fs::remove_file(file).await.context("failed to remove log file")?;
+        }
+    }
+
+    Ok(freed)
 }
 
 /// removes a package from the log store
@@ -51,3 +232,50 @@ pub async fn clean(package: &Package) -> Result<()> {
         Ok(())
     }
 }
+
+/// compresses all plain log files which were written before logs were
+/// compressed, returns the amount of migrated files
+pub async fn migrate_compression() -> Result<usize> {
+    let root = PathBuf::from(LOG_DIR);
+    if !root.exists() {
+        trace!("no log directory, skipping log compression migration");
+        return Ok(0);
+    }
+
+    let mut migrated = 0;
+
+    let mut packages = fs::read_dir(root).await.context("failed to read log directory")?;
+    while let Some(package) = packages.next_entry().await? {
+        if !package.file_type().await?.is_dir() {
+            continue;
+        }
+
+        let mut files = fs::read_dir(package.path()).await?;
+        while let Some(file) = files.next_entry().await? {
+            let plain = file.path();
+            if plain.extension().is_none_or(|e| e != PLAIN_EXTENSION) {
+                continue;
+            }
+
+            debug!("compressing build logs at {}", plain.display());
+
+            let compressed = plain.with_extension(COMPRESSED_EXTENSION);
+            let logs = fs::read(&plain).await.context("failed to read plain log file")?;
+
+            // the plain file is still there if this fails
+            if let Err(e) = compress(&compressed, &logs).await {
+                warn!("failed to compress build logs at {}: {e:#}", plain.display());
+                continue;
+            }
+
+            fs::remove_file(&plain).await.context("failed to remove plain log file")?;
+            migrated += 1;
+        }
+    }
+
+    if migrated > 0 {
+        info!("compressed {migrated} plain build logs");
+    }
+
+    Ok(migrated)
+}
@@ -1,6 +1,9 @@
+pub mod audit;
 pub mod build;
 pub mod log;
 pub mod package;
+pub mod retention;
+pub mod secret;
 
 use ::log::info;
 use anyhow::Context;
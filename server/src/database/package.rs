@@ -3,9 +3,10 @@ use crate::package::source::legacy::LegacySource;
 use crate::package::srcinfo::{SrcinfoGeneratorInstance, SrcinfoWrapper};
 use crate::package::{Package, SOURCE_FOLDER};
 use anyhow::{Context, Result};
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Utc};
 use log::info;
 use serde_json::Value;
+use serene_data::package::NotifyRule;
 use sqlx::{query, query_as};
 use std::path::Path;
 use std::str::FromStr;
@@ -16,6 +17,11 @@ use std::str::FromStr;
 /// server/migrations/20241004212454_built_state.sql
 /// server/migrations/20241007180807_remove_version.sql
 /// server/migrations/20250418161813_private.sql
+/// server/migrations/20261016093512_timeout.sql
+/// server/migrations/20261016141027_limits.sql
+/// server/migrations/20261016203151_notify.sql
+/// server/migrations/20261016231703_webhook.sql
+/// server/migrations/20261016235511_review.sql
 #[derive(Debug)]
 struct PackageRecord {
     /// id
@@ -33,6 +39,16 @@ struct PackageRecord {
     schedule: Option<String>,
     prepare: Option<String>,
     flags: Option<String>,
+    timeout: Option<i64>,
+    limits: Option<String>,
+    notify: Option<String>,
+    webhook_nonce: Option<String>,
+    webhook_fired: Option<NaiveDateTime>,
+    review: bool,
+    built_files: Option<String>,
+    review_pending: Option<String>,
+    review_approved: Option<String>,
+    review_rejected: Option<String>,
 }
 
 impl DatabaseConversion<PackageRecord> for Package {
@@ -55,6 +71,32 @@ impl DatabaseConversion<PackageRecord> for Package {
                 None
             },
             dependency: self.dependency,
+            timeout: self.timeout.map(|t| t as i64),
+            limits: if !self.limits.is_empty() {
+                Some(serde_json::to_string(&self.limits).context("failed to serialize limits")?)
+            } else {
+                None
+            },
+            notify: self.notify.map(|n| n.to_string()),
+            webhook_nonce: self.webhook_nonce.clone(),
+            webhook_fired: self.webhook_fired.map(|t| t.naive_utc()),
+            review: self.review,
+            built_files: if !self.built_files.is_empty() {
+                Some(
+                    serde_json::to_string(&self.built_files)
+                        .context("failed to serialize built files")?,
+                )
+            } else {
+                None
+            },
+            review_pending: self
+                .review_pending
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .context("failed to serialize pending review")?,
+            review_approved: self.review_approved.clone(),
+            review_rejected: self.review_rejected.clone(),
         })
     }
 
@@ -79,6 +121,27 @@ impl DatabaseConversion<PackageRecord> for Package {
                 .map(|s| serde_json::from_str(&s).context("failed to deserialize source"))
                 .unwrap_or_else(|| Ok(vec![]))?,
             dependency: value.dependency,
+            timeout: value.timeout.map(|t| t as u32),
+            limits: value
+                .limits
+                .map(|s| serde_json::from_str(&s).context("failed to deserialize limits"))
+                .transpose()?
+                .unwrap_or_default(),
+            notify: value.notify.and_then(|n| NotifyRule::from_str(&n).ok()),
+            webhook_nonce: value.webhook_nonce,
+            webhook_fired: value.webhook_fired.map(|t| t.and_utc()),
+            review: value.review,
+            built_files: value
+                .built_files
+                .map(|s| serde_json::from_str(&s).context("failed to deserialize built files"))
+                .transpose()?
+                .unwrap_or_default(),
+            review_pending: value
+                .review_pending
+                .map(|s| serde_json::from_str(&s).context("failed to deserialize pending review"))
+                .transpose()?,
+            review_approved: value.review_approved,
+            review_rejected: value.review_rejected,
         })
     }
 }
@@ -149,10 +212,10 @@ impl Package {
         let record = self.create_record()?;
 
         query!(r#"
-            INSERT INTO package (base, added, source, srcinfo, pkgbuild, enabled, clean, private, schedule, prepare, flags, dependency, built_state)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            INSERT INTO package (base, added, source, srcinfo, pkgbuild, enabled, clean, private, schedule, prepare, flags, dependency, built_state, timeout, limits, notify, webhook_nonce, review, built_files)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
         "#,
-            record.base, record.added, record.source, record.srcinfo, record.pkgbuild, record.enabled, record.clean, record.private, record.schedule, record.prepare, record.flags, record.dependency, record.built_state
+            record.base, record.added, record.source, record.srcinfo, record.pkgbuild, record.enabled, record.clean, record.private, record.schedule, record.prepare, record.flags, record.dependency, record.built_state, record.timeout, record.limits, record.notify, record.webhook_nonce, record.review, record.built_files
         )
             .execute(db).await?;
 
@@ -166,7 +229,7 @@ impl Package {
         query!(
             r#"
             UPDATE package
-            SET enabled = $2, clean = $3, private = $4, schedule = $5, prepare = $6, flags = $7, dependency = $8
+            SET enabled = $2, clean = $3, private = $4, schedule = $5, prepare = $6, flags = $7, dependency = $8, timeout = $9, limits = $10, notify = $11, review = $12
             WHERE base = $1
         "#,
             record.base,
@@ -176,7 +239,11 @@ impl Package {
             record.schedule,
             record.prepare,
             record.flags,
-            record.dependency
+            record.dependency,
+            record.timeout,
+            record.limits,
+            record.notify,
+            record.review
         )
         .execute(db)
         .await?;
@@ -191,14 +258,72 @@ impl Package {
         query!(
             r#"
             UPDATE package
-            SET source = $2, srcinfo = $3, pkgbuild = $4, built_state = $5
+            SET source = $2, srcinfo = $3, pkgbuild = $4, built_state = $5, built_files = $6
             WHERE base = $1
         "#,
             record.base,
             record.source,
             record.srcinfo,
             record.pkgbuild,
-            record.built_state
+            record.built_state,
+            record.built_files
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates the webhook nonce inside the database
+    pub async fn change_webhook(&self, db: &Database) -> Result<()> {
+        query!(
+            r#"
+            UPDATE package
+            SET webhook_nonce = $2
+            WHERE base = $1
+        "#,
+            self.base,
+            self.webhook_nonce
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates the state of the review of the build files inside the database
+    pub async fn change_review(&self, db: &Database) -> Result<()> {
+        let record = self.create_record()?;
+
+        query!(
+            r#"
+            UPDATE package
+            SET review_pending = $2, review_approved = $3, review_rejected = $4
+            WHERE base = $1
+        "#,
+            record.base,
+            record.review_pending,
+            record.review_approved,
+            record.review_rejected
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates when a webhook for the package was last received
+    pub async fn mark_webhook_fired(&self, db: &Database) -> Result<()> {
+        let now = Utc::now().naive_utc();
+
+        query!(
+            r#"
+            UPDATE package
+            SET webhook_fired = $2
+            WHERE base = $1
+        "#,
+            self.base,
+            now
         )
         .execute(db)
         .await?;
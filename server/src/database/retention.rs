@@ -0,0 +1,52 @@
+use crate::build::BuildSummary;
+use crate::config::CONFIG;
+use crate::database::Database;
+use crate::package::Package;
+use chrono::{Duration, Utc};
+use log::{debug, info};
+use serene_data::PurgeInfo;
+use serene_data::build::BuildState;
+
+/// removes the builds and their logs which are no longer retained by the
+/// configured rules, the last successful and failed build are always kept
+pub async fn purge(db: &Database) -> anyhow::Result<PurgeInfo> {
+    let mut result = PurgeInfo { builds: 0, freed: 0 };
+
+    let oldest = CONFIG.retention_max_age.map(|days| Utc::now() - Duration::days(days as i64));
+
+    for package in Package::find_all(db).await? {
+        // these are ordered from newest to oldest
+        let builds = BuildSummary::find_all_for_package(&package.base, db).await?;
+
+        let last_success =
+            builds.iter().find(|b| matches!(b.state, BuildState::Success)).map(|b| b.started);
+        let last_failure = builds
+            .iter()
+            .find(|b| matches!(b.state, BuildState::Failure | BuildState::Fatal(_, _)))
+            .map(|b| b.started);
+
+        for (i, build) in builds.iter().enumerate() {
+            if !build.state.done()
+                || Some(build.started) == last_success
+                || Some(build.started) == last_failure
+            {
+                continue;
+            }
+
+            let too_many = CONFIG.retention_max_builds.is_some_and(|max| i >= max);
+            let too_old = oldest.is_some_and(|oldest| build.started < oldest);
+
+            if too_many || too_old {
+                debug!("purging build of {} started at {}", build.package, build.started);
+
+                result.freed += super::log::remove(build).await?;
+                build.delete(db).await?;
+                result.builds += 1;
+            }
+        }
+    }
+
+    info!("purged {} old builds, freeing {} bytes of logs", result.builds, result.freed);
+
+    Ok(result)
+}
@@ -0,0 +1,217 @@
+use crate::database::{Database, DatabaseConversion};
+use crate::web::auth::AuthorizedSecret;
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDateTime, Utc};
+use log::{info, trace};
+use serene_data::secret::Scope;
+use sqlx::{query, query_as};
+use std::path::Path;
+use std::str::FromStr;
+
+/// file the secrets were configured in before they were stored in the database
+const AUTHORIZED_PATH: &str = "authorized_secrets";
+
+/// See migrations:
+/// server/migrations/20261016214407_secret.sql
+#[derive(Debug)]
+struct SecretRecord {
+    id: i64,
+    hash: String,
+    comment: String,
+
+    scopes: String,
+    packages: Option<String>,
+
+    created: NaiveDateTime,
+    used: Option<NaiveDateTime>,
+    revoked: Option<NaiveDateTime>,
+}
+
+impl DatabaseConversion<SecretRecord> for AuthorizedSecret {
+    fn create_record(&self) -> Result<SecretRecord> {
+        Ok(SecretRecord {
+            id: self.id,
+            hash: self.hash.clone(),
+            comment: self.comment.clone(),
+            scopes: self.scopes.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(","),
+            packages: (!self.packages.is_empty()).then(|| self.packages.join(",")),
+            created: self.created.naive_utc(),
+            used: self.used.map(|t| t.naive_utc()),
+            revoked: None,
+        })
+    }
+
+    fn from_record(other: SecretRecord) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            id: other.id,
+            hash: other.hash,
+            comment: other.comment,
+            scopes: other.scopes.split(',').filter_map(|s| Scope::from_str(s).ok()).collect(),
+            packages: other
+                .packages
+                .map(|p| p.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            created: other.created.and_utc(),
+            used: other.used.map(|t| t.and_utc()),
+        })
+    }
+}
+
+impl AuthorizedSecret {
+    /// Returns whether the database contains a secret with the given hash,
+    /// including revoked ones
+    pub async fn has(hash: &str, db: &Database) -> Result<bool> {
+        let amount = query!(
+            r#"
+            SELECT COUNT(id) as count FROM secret WHERE hash == $1
+        "#,
+            hash
+        )
+        .fetch_one(db)
+        .await?
+        .count;
+
+        Ok(amount > 0)
+    }
+
+    /// Find an authorized secret by its hash
+    pub async fn find(hash: &str, db: &Database) -> Result<Option<Self>> {
+        let record = query_as!(
+            SecretRecord,
+            r#"
+            SELECT * FROM secret WHERE hash = $1 AND revoked IS NULL
+        "#,
+            hash
+        )
+        .fetch_optional(db)
+        .await?;
+
+        record.map(AuthorizedSecret::from_record).transpose()
+    }
+
+    /// Find an authorized secret by its id
+    pub async fn find_by_id(id: i64, db: &Database) -> Result<Option<Self>> {
+        let record = query_as!(
+            SecretRecord,
+            r#"
+            SELECT * FROM secret WHERE id = $1 AND revoked IS NULL
+        "#,
+            id
+        )
+        .fetch_optional(db)
+        .await?;
+
+        record.map(AuthorizedSecret::from_record).transpose()
+    }
+
+    /// Find all authorized secrets
+    pub async fn find_all(db: &Database) -> Result<Vec<Self>> {
+        let records = query_as!(
+            SecretRecord,
+            r#"
+            SELECT * FROM secret WHERE revoked IS NULL ORDER BY id ASC
+        "#
+        )
+        .fetch_all(db)
+        .await?;
+
+        records.into_iter().map(AuthorizedSecret::from_record).collect()
+    }
+
+    /// Saves the secret to the database, authorizing it again if it was
+    /// revoked before
+    pub async fn save(&self, db: &Database) -> Result<()> {
+        let record = self.create_record()?;
+
+        query!(
+            r#"
+            INSERT INTO secret (hash, comment, scopes, packages, created)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (hash) DO UPDATE
+            SET comment = $2, scopes = $3, packages = $4, created = $5, used = NULL, revoked = NULL
+        "#,
+            record.hash,
+            record.comment,
+            record.scopes,
+            record.packages,
+            record.created
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates when the secret was last used, this only happens once a minute
+    /// to not write to the database on every request
+    pub async fn mark_used(&self, db: &Database) -> Result<()> {
+        let now = Utc::now();
+        if self.used.is_some_and(|used| now - used < Duration::minutes(1)) {
+            return Ok(());
+        }
+
+        let now = now.naive_utc();
+
+        query!(
+            r#"
+            UPDATE secret SET used = $2 WHERE id = $1
+        "#,
+            self.id,
+            now
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revokes the secret, it is kept in the database so it is not imported
+    /// from the file again
+    pub async fn revoke(&self, db: &Database) -> Result<()> {
+        let now = Utc::now().naive_utc();
+
+        query!(
+            r#"
+            UPDATE secret SET revoked = $2 WHERE id = $1
+        "#,
+            self.id,
+            now
+        )
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// imports the secrets of the authorized secrets file into the database,
+/// secrets which are already known or were revoked are skipped
+pub async fn migrate_file(db: &Database) -> Result<usize> {
+    if !Path::new(AUTHORIZED_PATH).is_file() {
+        trace!("no authorized secrets file, skipping secret import");
+        return Ok(0);
+    }
+
+    let file = tokio::fs::read_to_string(AUTHORIZED_PATH)
+        .await
+        .context("failed to read authorized secrets")?;
+
+    let mut imported = 0;
+    for secret in file.lines().filter_map(AuthorizedSecret::parse) {
+        if AuthorizedSecret::has(&secret.hash, db).await? {
+            continue;
+        }
+
+        secret.save(db).await.context("failed to save imported secret")?;
+        imported += 1;
+    }
+
+    if imported > 0 {
+        info!("imported {imported} secrets from the authorized secrets file");
+    }
+
+    Ok(imported)
+}
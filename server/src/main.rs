@@ -19,6 +19,7 @@ use crate::package::srcinfo::SrcinfoGenerator;
 use crate::package::{Package, migrate_build_state};
 use crate::repository::PackageRepository;
 use crate::runner::Runner;
+use crate::runner::retention::RetentionScheduler;
 use crate::runner::update::ImageScheduler;
 use crate::web::broadcast::Broadcast;
 use actix_web::web::Data;
@@ -56,6 +57,13 @@ async fn main() -> anyhow::Result<()> {
     }
     let db = db;
 
+    // compressing old plain logs can take a while, so it is done in the background
+    tokio::spawn(async {
+        if let Err(e) = database::log::migrate_compression().await {
+            error!("failed to compress all build logs: {e:#}");
+        }
+    });
+
     // initialize broadcast
     let broadcast = Broadcast::new();
 
@@ -90,6 +98,9 @@ async fn main() -> anyhow::Result<()> {
     // creating image scheduler
     let image_scheduler = ImageScheduler::new(runner.clone());
 
+    // creating retention scheduler
+    let retention_scheduler = RetentionScheduler::new(db.clone());
+
     // yes, this will wait before starting the api, because after updates stuff
     // can't be built without a new image
     // we'll do this before the migrations because some need the new container
@@ -97,9 +108,10 @@ async fn main() -> anyhow::Result<()> {
     image_scheduler.run_sync().await;
 
     // cleanup unfinished builds
-    if let Err(e) = cleanup_unfinished(&db).await {
-        error!("failed to cleanup unfinished builds: {e:#}")
-    }
+    let unfinished = cleanup_unfinished(&db).await.unwrap_or_else(|e| {
+        error!("failed to cleanup unfinished builds: {e:#}");
+        vec![]
+    });
 
     // migrations
     if let Err(e) = migrate_build_state(&db).await {
@@ -108,6 +120,10 @@ async fn main() -> anyhow::Result<()> {
 
     migrate_sources(&db, &srcinfo_generator).await?; // we should panic if it fails
 
+    if let Err(e) = database::secret::migrate_file(&db).await {
+        error!("failed to import authorized secrets: {e:#}")
+    }
+
     repository::remove_orphan_signature().await;
 
     // schedule packages (which are enabled)
@@ -125,6 +141,7 @@ async fn main() -> anyhow::Result<()> {
     }
 
     image_scheduler.start().await?;
+    retention_scheduler.start().await?;
 
     if !CONFIG.scheduling_disabled {
         schedule.start().await?;
@@ -132,6 +149,12 @@ async fn main() -> anyhow::Result<()> {
         warn!("skipping starting the package scheduler, packages will not be built automatically");
     }
 
+    if CONFIG.requeue_unfinished
+        && let Err(e) = schedule.requeue(unfinished).await
+    {
+        error!("failed to requeue unfinished builds: {e:#}")
+    }
+
     let schedule = Arc::new(Mutex::new(schedule));
 
     info!("serene started successfully on port {}!", CONFIG.port);
@@ -154,15 +177,40 @@ async fn main() -> anyhow::Result<()> {
             .service(web::remove)
             .service(web::build_all)
             .service(web::build)
+            .service(web::stop_build)
+            .service(web::queue)
+            .service(web::purge)
+            .service(web::list_secrets)
+            .service(web::add_secret)
+            .service(web::revoke_secret)
+            .service(web::audit)
+            .service(web::manifest::get_manifest)
+            .service(web::manifest::apply_manifest)
             .service(web::get_all_builds)
             .service(web::get_build)
             .service(web::get_logs)
+            .service(web::get_log_sections)
+            .service(web::get_log_section)
             .service(web::subscribe_logs)
+            .service(web::subscribe_events)
             .service(web::settings)
+            .service(web::trust)
             .service(web::pkgbuild)
+            .service(web::diff)
+            .service(web::review::get_review)
+            .service(web::review::review)
             .service(web::get_webhook_secret)
+            .service(web::rotate_webhook_secret)
             .service(web::build_webhook)
+            .service(web::webhook::push_webhook)
             .service(web::get_signature_public_key)
+            .service(web::metrics::metrics)
+            .service(web::ui::ui_list)
+            .service(web::ui::ui_package)
+            .service(web::ui::ui_build)
+            .service(web::ui::ui_login_form)
+            .service(web::ui::ui_login)
+            .service(web::ui::ui_logout)
     })
     .bind(("0.0.0.0", CONFIG.port))?
     .run()
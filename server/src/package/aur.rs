@@ -1,6 +1,7 @@
 use crate::config::CONFIG;
 use crate::package::git;
 use crate::package::srcinfo::SrcinfoWrapper;
+use crate::web::metrics::METRICS;
 use anyhow::{anyhow, Context};
 use log::{debug, warn};
 use raur::{Package, Raur};
@@ -24,7 +25,10 @@ pub async fn info(name: &str) -> anyhow::Result<Option<Package>> {
     let handle = handle()?;
 
     for t in 0..=CONFIG.aur_request_retries {
-        match handle.info(&[name]).await {
+        let result = handle.info(&[name]).await;
+        METRICS.aur_request(result.is_ok());
+
+        match result {
             Ok(pkg) => return Ok(pkg.into_iter().next()),
 
             Err(e) if t == CONFIG.aur_request_retries => {
@@ -0,0 +1,111 @@
+use crate::package::Package;
+use crate::package::source::PKGBUILD;
+use anyhow::{Context, anyhow};
+use log::debug;
+use similar::TextDiff;
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+use tokio::fs;
+
+/// contents of the build files of a package by their name
+pub type BuildFiles = BTreeMap<String, String>;
+
+/// reads the build files of the next build of a package, these are the
+/// PKGBUILD, install scripts and local sources like patches
+pub async fn read(package: &Package) -> anyhow::Result<BuildFiles> {
+    let folder = package.get_folder();
+    let srcinfo = package.get_next_srcinfo().await?;
+
+    let mut files = BuildFiles::new();
+    files.insert(PKGBUILD.to_string(), package.source.get_pkgbuild(&folder).await?);
+
+    let installs = srcinfo.pkgs.iter().chain([&srcinfo.pkg]).filter_map(|p| p.install.as_deref());
+
+    // sources without an url are files next to the pkgbuild
+    let locals = srcinfo
+        .base
+        .source
+        .iter()
+        .flat_map(|s| s.values())
+        .map(|s| s.split_once("::").map(|(_, location)| location).unwrap_or(s))
+        .filter(|s| !s.contains("://"));
+
+    for name in installs.chain(locals) {
+        if files.contains_key(name) {
+            continue;
+        }
+
+        // binary or missing files can't be shown anyway
+        match read_local(&folder, name).await {
+            Ok(content) => {
+                files.insert(name.to_string(), content);
+            }
+            Err(e) => debug!("not including build file {name} of {}: {e:#}", package.base),
+        }
+    }
+
+    Ok(files)
+}
+
+/// reads a file named by the srcinfo from the source folder, making sure it
+/// doesn't point outside of it
+async fn read_local(folder: &Path, name: &str) -> anyhow::Result<String> {
+    let relative = Path::new(name);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(anyhow!("file is outside of the source folder"));
+    }
+
+    let path = folder.join(relative);
+
+    // symlinks could point anywhere on the server
+    let metadata = fs::symlink_metadata(&path).await.context("failed to read file metadata")?;
+    if !metadata.file_type().is_file() {
+        return Err(anyhow!("file is not a regular file"));
+    }
+
+    let root = fs::canonicalize(folder).await.context("failed to resolve source folder")?;
+    let path = fs::canonicalize(&path).await.context("failed to resolve file")?;
+    if !path.starts_with(&root) {
+        return Err(anyhow!("file is outside of the source folder"));
+    }
+
+    fs::read_to_string(path).await.context("failed to read file")
+}
+
+/// returns the build files of the currently passing build, none if the package
+/// was never built
+pub fn built(package: &Package) -> Option<BuildFiles> {
+    if !package.built_files.is_empty() {
+        return Some(package.built_files.clone());
+    }
+
+    // packages built before the files were stored only know their pkgbuild
+    Some(BuildFiles::from([(PKGBUILD.to_string(), package.pkgbuild.clone()?)]))
+}
+
+/// creates a unified diff of all changed build files
+pub fn diff(old: &BuildFiles, new: &BuildFiles) -> String {
+    let mut names = old.keys().chain(new.keys()).collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+
+    let mut diff = String::new();
+    for name in names {
+        let (before, after) = (old.get(name), new.get(name));
+        if before == after {
+            continue;
+        }
+
+        let from = if before.is_some() { format!("a/{name}") } else { "/dev/null".to_string() };
+        let to = if after.is_some() { format!("b/{name}") } else { "/dev/null".to_string() };
+
+        let text = TextDiff::from_lines(
+            before.map(String::as_str).unwrap_or_default(),
+            after.map(String::as_str).unwrap_or_default(),
+        );
+
+        diff.push_str(&text.unified_diff().header(&from, &to).to_string());
+    }
+
+    diff
+}
@@ -54,6 +54,30 @@ pub async fn find_local_commit(directory: &Path) -> anyhow::Result<String> {
     }
 }
 
+/// finds the branch which is checked out in a repository, none if the head is
+/// detached
+pub async fn find_local_branch(directory: &Path) -> anyhow::Result<Option<String>> {
+    let status = Command::new("git")
+        .arg("rev-parse")
+        .arg("--abbrev-ref")
+        .arg("HEAD")
+        .current_dir(directory)
+        .output()
+        .await?;
+
+    if status.status.success() {
+        let branch = String::from_utf8_lossy(&status.stdout).trim().to_owned();
+
+        // git names the head itself if no branch is checked out
+        Ok((branch != "HEAD").then_some(branch))
+    } else {
+        Err(anyhow!(
+            "failed to find branch of git repository: {}",
+            String::from_utf8_lossy(&status.stderr)
+        ))
+    }
+}
+
 // finds the version of the git remote, given a git url.
 // The url should be in the format described here (without the directory and git+): https://man.archlinux.org/man/PKGBUILD.5#USING_VCS_SOURCES
 pub async fn find_remote_commit(url: &str) -> anyhow::Result<String> {
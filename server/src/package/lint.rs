@@ -0,0 +1,316 @@
+use crate::config::CONFIG;
+use crate::package::Package;
+use crate::package::files::BuildFiles;
+use crate::package::source::PKGBUILD;
+use crate::package::srcinfo::SrcinfoWrapper;
+use serene_data::build::{LintFinding, LintRule, LintSeverity};
+use std::collections::HashSet;
+
+/// programs which download something
+const DOWNLOADERS: [&str; 2] = ["curl", "wget"];
+/// programs which run what is piped into them
+const SHELLS: [&str; 8] = ["sh", "bash", "zsh", "dash", "fish", "python", "python3", "perl"];
+/// programs which write to their last argument
+const WRITERS: [&str; 4] = ["install", "cp", "mv", "ln"];
+/// programs which write to all of their arguments
+const CREATORS: [&str; 4] = ["mkdir", "touch", "tee", "rm"];
+/// schemes of vcs sources, which can't have checksums
+const VCS: [&str; 5] = ["git", "svn", "hg", "bzr", "fossil"];
+/// extensions of signatures, which are checked with pgp instead
+const SIGNATURES: [&str; 3] = [".sig", ".asc", ".sign"];
+
+/// minimal length of a word to be considered a base64 blob
+const BLOB_LENGTH: usize = 120;
+/// maximal length of a line shown with a finding
+const EXCERPT_LENGTH: usize = 80;
+
+/// collects the findings of the rules which are not turned off
+struct Findings(Vec<LintFinding>);
+
+impl Findings {
+    fn add(&mut self, rule: LintRule, file: &str, message: String) {
+        let severity = CONFIG.get_lint_severity(rule);
+
+        if severity != LintSeverity::Off {
+            self.0.push(LintFinding { rule, severity, file: file.to_string(), message });
+        }
+    }
+}
+
+/// shortens a line so it can be shown with a finding
+fn excerpt(line: &str) -> String {
+    let line = line.trim();
+
+    if line.chars().count() > EXCERPT_LENGTH {
+        format!("{}...", line.chars().take(EXCERPT_LENGTH).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// returns the name of a program invoked by a word, e.g. `curl` for `/usr/bin/curl`
+fn program(word: &str) -> &str {
+    word.rsplit('/').next().unwrap_or(word)
+}
+
+/// returns the words of a shell statement without quotes
+fn words(statement: &str) -> Vec<&str> {
+    statement
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c| c == '"' || c == '\''))
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// whether something is downloaded and run by a shell, like `curl ... | sh`
+/// or `bash <(curl ...)`
+fn pipes_to_shell(line: &str) -> bool {
+    // `a || b` does not pipe anything
+    let line = line.replace("||", ";");
+
+    let mut downloaded = false;
+    for segment in line.split('|') {
+        let words = words(segment);
+        let mut commands = words.iter().filter(|w| **w != "sudo").map(|w| program(w));
+
+        if downloaded && commands.next().is_some_and(|c| SHELLS.contains(&c)) {
+            return true;
+        }
+
+        downloaded = words.iter().any(|w| DOWNLOADERS.contains(&program(w)));
+    }
+
+    let substituted = DOWNLOADERS
+        .iter()
+        .any(|d| line.contains(&format!("<({d}")) || line.contains(&format!("$({d}")));
+    let executed = words(&line).iter().any(|w| *w == "eval" || SHELLS.contains(&program(w)));
+
+    substituted && executed
+}
+
+/// whether a path points outside of the build directories, which are always
+/// referenced with variables like `$pkgdir`
+fn outside(path: &str) -> bool {
+    if path.starts_with("/dev/") {
+        return false;
+    }
+
+    path.starts_with('/')
+        || path.starts_with('~')
+        || path.starts_with("$HOME")
+        || path.starts_with("${HOME}")
+}
+
+/// returns the first path written outside of the build directories by a line
+fn writes_outside(line: &str) -> Option<String> {
+    for statement in line.split([';', '|', '&']) {
+        let words = words(statement);
+
+        // redirections like `> /etc/file` or `>>/etc/file`
+        for (i, word) in words.iter().enumerate() {
+            let Some(index) = word.find('>') else { continue };
+
+            let target = word[index..].trim_start_matches('>');
+            let target = if target.is_empty() { words.get(i + 1).copied() } else { Some(target) };
+
+            if let Some(target) = target.map(|t| t.trim_matches(|c| c == '"' || c == '\''))
+                && outside(target)
+            {
+                return Some(target.to_string());
+            }
+        }
+
+        let mut commands = words.iter().skip_while(|w| **w == "sudo");
+        let Some(command) = commands.next().map(|c| program(c)) else { continue };
+        let mut arguments = commands.filter(|w| !w.starts_with('-') && !w.contains('>')).copied();
+
+        let target = if WRITERS.contains(&command) {
+            arguments.last().filter(|a| outside(a))
+        } else if CREATORS.contains(&command) {
+            arguments.find(|a| outside(a))
+        } else {
+            None
+        };
+
+        if let Some(target) = target {
+            return Some(target.to_string());
+        }
+    }
+
+    None
+}
+
+/// whether a word looks like base64 encoded data
+fn is_blob(word: &str) -> bool {
+    word.len() >= BLOB_LENGTH
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '=')
+        // hashes are hex, so they only have one case
+        && word.chars().any(|c| c.is_ascii_lowercase())
+        && word.chars().any(|c| c.is_ascii_uppercase())
+}
+
+/// checks the lines of a script, which is either the PKGBUILD or an install script
+fn lint_script(findings: &mut Findings, file: &str, content: &str) {
+    for (number, line) in (1..).zip(content.lines()) {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if pipes_to_shell(line) {
+            findings.add(
+                LintRule::PipeToShell,
+                file,
+                format!("downloads something into a shell on line {number}: `{}`", excerpt(line)),
+            );
+        }
+
+        // install scripts run on the host, so they are expected to write anywhere
+        if file == PKGBUILD
+            && let Some(path) = writes_outside(line)
+        {
+            findings.add(
+                LintRule::OutsidePkgdir,
+                file,
+                format!(
+                    "writes to {path} outside of $pkgdir on line {number}: `{}`",
+                    excerpt(line)
+                ),
+            );
+        }
+
+        let blob = line.split(|c: char| c.is_whitespace() || "\"'=()".contains(c)).any(is_blob);
+        if blob || line.contains("base64 -d") || line.contains("base64 --decode") {
+            findings.add(
+                LintRule::EncodedBlob,
+                file,
+                format!("contains or decodes base64 data on line {number}: `{}`", excerpt(line)),
+            );
+        }
+    }
+}
+
+/// returns the location of a source entry, without its name
+fn location(source: &str) -> &str {
+    source.split_once("::").map(|(_, location)| location).unwrap_or(source)
+}
+
+/// returns the domain of a remote source location
+fn domain(location: &str) -> Option<String> {
+    let (_, rest) = location.split_once("://")?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+/// returns the domains of all sources of a srcinfo
+fn domains(srcinfo: &SrcinfoWrapper) -> HashSet<String> {
+    srcinfo
+        .base
+        .source
+        .iter()
+        .flat_map(|s| s.values())
+        .filter_map(|s| domain(location(s)))
+        .collect()
+}
+
+/// checks the sources and their checksums
+fn lint_sources(findings: &mut Findings, next: &SrcinfoWrapper, built: Option<&SrcinfoWrapper>) {
+    let base = &next.base;
+    let checksums = [
+        &base.md5sums,
+        &base.sha1sums,
+        &base.sha224sums,
+        &base.sha256sums,
+        &base.sha384sums,
+        &base.sha512sums,
+        &base.b2sums,
+    ];
+
+    let known = built.map(domains);
+    let mut reported = HashSet::new();
+
+    for sources in &base.source {
+        for (index, source) in (0..).zip(sources.values()) {
+            let source: &str = source;
+            let location = location(source);
+
+            let Some((scheme, _)) = location.split_once("://") else {
+                // local files are part of the reviewed build files
+                continue;
+            };
+
+            // checksums are listed in the same order as the sources of the same architecture
+            let sums = checksums
+                .iter()
+                .filter_map(|kind| {
+                    kind.iter()
+                        .filter(|c| c.arch() == sources.arch())
+                        .flat_map(|c| c.values())
+                        .nth(index)
+                })
+                .collect::<Vec<_>>();
+
+            let skipped = sums.iter().all(|s| s.eq_ignore_ascii_case("SKIP"));
+            let vcs = scheme.contains('+') || VCS.contains(&scheme);
+            let signature = SIGNATURES.iter().any(|s| location.ends_with(s));
+
+            if skipped && (scheme == "http" || scheme.ends_with("+http")) {
+                findings.add(
+                    LintRule::PlainHttp,
+                    PKGBUILD,
+                    format!("source {location} is downloaded over plain http without a checksum"),
+                );
+            } else if skipped && !vcs && !signature {
+                findings.add(
+                    LintRule::SkippedChecksum,
+                    PKGBUILD,
+                    format!("checksum of source {location} is skipped"),
+                );
+            }
+
+            if let Some(known) = &known
+                && let Some(host) = domain(location)
+                && !known.contains(&host)
+                && reported.insert(host.clone())
+            {
+                findings.add(
+                    LintRule::NewDomain,
+                    PKGBUILD,
+                    format!("source {location} is downloaded from {host}, which the built version did not use"),
+                );
+            }
+        }
+    }
+}
+
+/// runs the heuristic checks on the build files and the srcinfo of the next
+/// build, new domains are found by comparing with the built srcinfo
+pub fn lint(
+    files: &BuildFiles,
+    next: &SrcinfoWrapper,
+    built: Option<&SrcinfoWrapper>,
+) -> Vec<LintFinding> {
+    let mut findings = Findings(vec![]);
+
+    // local sources like patches are not run, so only the scripts are checked
+    for (file, content) in files {
+        if file == PKGBUILD
+            || next.pkgs.iter().chain([&next.pkg]).any(|p| p.install.as_ref() == Some(file))
+        {
+            lint_script(&mut findings, file, content);
+        }
+    }
+
+    lint_sources(&mut findings, next, built);
+
+    findings.0
+}
+
+/// runs the heuristic checks on the build files of the next build of a package
+pub async fn check(package: &Package, files: &BuildFiles) -> anyhow::Result<Vec<LintFinding>> {
+    let next = package.get_next_srcinfo().await?;
+
+    Ok(lint(files, &next, package.srcinfo.as_ref()))
+}
@@ -2,22 +2,28 @@ use crate::build::schedule::{BuildMeta, BuildScheduler};
 use crate::build::BuildSummary;
 use crate::config::{CLI_PACKAGE_NAME, CONFIG};
 use crate::database::Database;
+use crate::package::files::BuildFiles;
+use crate::package::review::PendingReview;
 use crate::package::source::Source;
 use crate::package::srcinfo::{SrcinfoGeneratorInstance, SrcinfoWrapper};
 use crate::resolve::AurResolver;
 use crate::runner;
 use crate::runner::archive::InputArchive;
+use crate::web::auth::generate_webhook_nonce;
 use anyhow::{anyhow, Context};
 use chrono::{DateTime, Utc};
 use log::{debug, info, warn};
 use serene_data::build::{BuildReason, BuildState};
-use serene_data::package::MakepkgFlag;
+use serene_data::package::{MakepkgFlag, NotifyRule, ResourceLimits};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::fs;
 
 pub mod aur;
+pub mod files;
 pub mod git;
+pub mod lint;
+pub mod review;
 pub mod source;
 pub mod srcinfo;
 
@@ -234,6 +240,30 @@ pub struct Package {
     pub prepare: Option<String>,
     /// special makepkg flags
     pub flags: Vec<MakepkgFlag>,
+    /// potential custom build timeout in minutes, zero disables the timeout
+    pub timeout: Option<u32>,
+    /// custom resource limits for the build container, zero disables a limit
+    pub limits: ResourceLimits,
+    /// potential custom rule on which notifications are sent
+    pub notify: Option<NotifyRule>,
+
+    /// random nonce the webhook secrets of the package are derived with,
+    /// packages added before it existed don't have one
+    pub webhook_nonce: Option<String>,
+    /// when a webhook for the package was last received
+    pub webhook_fired: Option<DateTime<Utc>>,
+
+    /// whether changes to the build files have to be approved before building
+    pub review: bool,
+    /// contents of the build files (PKGBUILD, install scripts and local
+    /// sources) of the currently passing build, empty for older packages
+    pub built_files: BuildFiles,
+    /// changes to the build files which are awaiting review
+    pub review_pending: Option<PendingReview>,
+    /// fingerprint of the build files which were last approved
+    pub review_approved: Option<String>,
+    /// fingerprint of the build files which were last rejected
+    pub review_rejected: Option<String>,
 }
 
 impl Package {
@@ -250,6 +280,18 @@ impl Package {
             schedule: None,
             prepare: None,
             flags: vec![],
+            timeout: None,
+            limits: ResourceLimits::default(),
+            notify: None,
+
+            webhook_nonce: Some(generate_webhook_nonce()),
+            webhook_fired: None,
+
+            review: false,
+            built_files: BuildFiles::new(),
+            review_pending: None,
+            review_approved: None,
+            review_rejected: None,
 
             srcinfo: None,
             pkgbuild: None,
@@ -260,7 +302,7 @@ impl Package {
     }
 
     /// gets the current folder for the source for the package
-    fn get_folder(&self) -> PathBuf {
+    pub(crate) fn get_folder(&self) -> PathBuf {
         Path::new(SOURCE_FOLDER).join(&self.base)
     }
 
@@ -278,6 +320,29 @@ impl Package {
             .clone()
     }
 
+    /// gets the build timeout in minutes for the package, if it has one
+    pub fn get_timeout(&self) -> Option<u32> {
+        self.timeout.or(CONFIG.build_timeout.map(|t| t as u32)).filter(|t| *t > 0)
+    }
+
+    /// gets the resource limits for the build container of the package
+    pub fn get_limits(&self) -> ResourceLimits {
+        ResourceLimits {
+            memory: self
+                .limits
+                .memory
+                .or(CONFIG.build_memory_limit.map(|m| m as u64))
+                .filter(|m| *m > 0),
+            cpus: self.limits.cpus.or(CONFIG.build_cpu_limit).filter(|c| *c > 0.0),
+            pids: self.limits.pids.or(CONFIG.build_pids_limit.map(|p| p as u64)).filter(|p| *p > 0),
+        }
+    }
+
+    /// gets the rule on which notifications are sent for the package
+    pub fn get_notify(&self) -> NotifyRule {
+        self.notify.unwrap_or(CONFIG.notify_rule)
+    }
+
     /// is the newest version of the package already built and in the repos
     pub fn newest_built(&self) -> bool {
         self.built_state == self.source.get_state()
@@ -295,6 +360,7 @@ impl Package {
     pub async fn upgrade(&mut self, reported: SrcinfoWrapper) -> anyhow::Result<()> {
         let mut srcinfo = self.source.get_srcinfo(&self.get_folder()).await?;
         let pkgbuild = self.source.get_pkgbuild(&self.get_folder()).await?;
+        let files = files::read(self).await?;
         let state = self.source.get_state();
 
         if self.source.devel || self.base == CLI_PACKAGE_NAME {
@@ -312,6 +378,7 @@ impl Package {
 
         self.srcinfo = Some(srcinfo);
         self.pkgbuild = Some(pkgbuild);
+        self.built_files = files;
         self.built_state = state;
 
         Ok(())
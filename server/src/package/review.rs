@@ -0,0 +1,101 @@
+use crate::build::schedule::BuildMeta;
+use crate::database::Database;
+use crate::package::Package;
+use crate::package::files::{self, BuildFiles};
+use crate::package::source::PKGBUILD;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serene_data::build::BuildReason;
+use serene_data::secret;
+
+/// extension of install scripts
+const INSTALL_EXTENSION: &str = ".install";
+
+/// changes to the build files of a package which have to be reviewed before
+/// the package is built
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingReview {
+    /// fingerprint of the build files under review
+    pub fingerprint: String,
+    /// unified diff against the build files of the last build
+    pub diff: String,
+    /// when the changes were found
+    pub created: DateTime<Utc>,
+}
+
+/// what should happen with a package after its build files were checked
+pub enum ReviewOutcome {
+    /// the build files are unchanged or approved, the package can be built
+    Proceed,
+    /// the build files changed and were put up for review
+    Held,
+    /// the build files are still awaiting review
+    Waiting,
+    /// the build files were rejected in a review
+    Rejected,
+}
+
+/// whether a build file is subject to reviews, these are the PKGBUILD and
+/// install scripts, which are run as root when installing
+fn reviewed(name: &str) -> bool {
+    name == PKGBUILD || name.ends_with(INSTALL_EXTENSION)
+}
+
+/// reads the build files of a package which are subject to reviews
+async fn review_files(package: &Package) -> anyhow::Result<BuildFiles> {
+    Ok(files::read(package).await?.into_iter().filter(|(name, _)| reviewed(name)).collect())
+}
+
+/// fingerprints a set of build files
+fn fingerprint(files: &BuildFiles) -> String {
+    secret::hash(&serde_json::to_string(files).unwrap_or_default())
+}
+
+/// checks whether the current build files of a package may be built, and puts
+/// them up for review if they changed since the last build
+pub async fn check(
+    package: &mut Package,
+    meta: &BuildMeta,
+    db: &Database,
+) -> anyhow::Result<ReviewOutcome> {
+    let files = review_files(package).await?;
+
+    // without the files of a previous build everything has to be reviewed
+    let baseline = package
+        .built_files
+        .iter()
+        .filter(|(name, _)| reviewed(name))
+        .map(|(name, content)| (name.clone(), content.clone()))
+        .collect::<BuildFiles>();
+
+    if files == baseline {
+        return Ok(ReviewOutcome::Proceed);
+    }
+
+    let fingerprint = fingerprint(&files);
+    let manual = meta.reason == BuildReason::Manual;
+
+    if package.review_approved.as_ref() == Some(&fingerprint) {
+        return Ok(ReviewOutcome::Proceed);
+    }
+
+    if let Some(pending) = &package.review_pending
+        && pending.fingerprint == fingerprint
+    {
+        return Ok(if manual { ReviewOutcome::Held } else { ReviewOutcome::Waiting });
+    }
+
+    // rejected changes can be reviewed again by building manually
+    if package.review_rejected.as_ref() == Some(&fingerprint) && !manual {
+        return Ok(ReviewOutcome::Rejected);
+    }
+
+    package.review_pending = Some(PendingReview {
+        diff: files::diff(&baseline, &files),
+        fingerprint,
+        created: Utc::now(),
+    });
+    package.change_review(db).await?;
+
+    Ok(ReviewOutcome::Held)
+}
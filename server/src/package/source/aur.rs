@@ -3,8 +3,10 @@ use crate::package::source::{Source, SourceImpl};
 use crate::package::{aur, git};
 use anyhow::Context;
 use async_trait::async_trait;
+use chrono::Utc;
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
+use serene_data::package::{MaintainerChange, PackageAddSource};
 use std::path::Path;
 use tokio::fs;
 
@@ -17,24 +19,123 @@ pub struct AurSource {
     /// whether the source is currently using the github mirror
     #[serde(default)]
     mirror: bool,
+
+    /// acknowledged maintainer of the package on the AUR
+    #[serde(default)]
+    maintainer: Option<String>,
+    /// whether the maintainer was recorded, older sources don't know it yet
+    #[serde(default)]
+    maintainer_recorded: bool,
+    /// change of the maintainer which was not acknowledged yet
+    #[serde(default)]
+    maintainer_change: Option<MaintainerChange>,
+    /// whether the maintainer could be looked up the last time the source
+    /// was checked
+    #[serde(default)]
+    maintainer_tracked: bool,
 }
 
 impl AurSource {
     pub fn new(base: &str) -> Self {
-        Self { base: base.to_owned(), version: "".to_owned(), mirror: CONFIG.aur_github_mirror }
+        Self {
+            base: base.to_owned(),
+            version: "".to_owned(),
+            mirror: CONFIG.aur_github_mirror,
+            maintainer: None,
+            maintainer_recorded: false,
+            maintainer_change: None,
+            maintainer_tracked: false,
+        }
     }
 
     pub fn migrated(base: String, version: String) -> Self {
-        Self { base, version, mirror: false }
+        Self {
+            base,
+            version,
+            mirror: false,
+            maintainer: None,
+            maintainer_recorded: false,
+            maintainer_change: None,
+            maintainer_tracked: false,
+        }
     }
 
-    /// reads the version of the package from the AUR RPC
-    pub async fn get_version_aur(&self) -> anyhow::Result<Option<String>> {
+    /// looks up the package on the AUR RPC, which is not done if we are using
+    /// the mirror
+    pub async fn get_info_aur(&self) -> anyhow::Result<Option<raur::Package>> {
         if self.mirror {
             return Ok(None);
         }
 
-        Ok(aur::info(&self.base).await?.map(|p| p.version))
+        aur::info(&self.base).await
+    }
+
+    /// looks up the maintainer of the package on the AUR RPC by the names of
+    /// its packages, since the rpc can't look up package bases
+    async fn lookup_maintainer(&self, folder: &Path) -> anyhow::Result<Option<Option<String>>> {
+        let mut names = vec![self.base.clone()];
+        if let Some(srcinfo) = self.get_srcinfo(folder).await? {
+            names.extend(srcinfo.pkgnames().filter(|n| *n != self.base).map(str::to_owned));
+        }
+
+        // the base was already looked up for the version if we are not using the mirror
+        for name in names.iter().skip(if self.mirror { 0 } else { 1 }) {
+            if let Some(info) = aur::info(name).await?
+                && info.package_base == self.base
+            {
+                return Ok(Some(info.maintainer));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// checks whether the maintainer of the package changed, independently of
+    /// where the version was read from
+    async fn track_maintainer(&mut self, info: Option<raur::Package>, folder: &Path) {
+        let maintainer = match info {
+            Some(info) => Ok(Some(info.maintainer)),
+            None => self.lookup_maintainer(folder).await,
+        };
+
+        match maintainer {
+            Ok(Some(maintainer)) => {
+                self.maintainer_tracked = true;
+                self.check_maintainer(maintainer);
+            }
+            Ok(None) => {
+                warn!("aur package {} not found over RPC, maintainer is not tracked", self.base);
+                self.maintainer_tracked = false;
+            }
+            Err(e) => {
+                warn!("failed to look up maintainer of aur package {}: {e:#}", self.base);
+                self.maintainer_tracked = false;
+            }
+        }
+    }
+
+    /// compares the maintainer with the acknowledged one, and records a change
+    fn check_maintainer(&mut self, maintainer: Option<String>) {
+        // the first seen maintainer is trusted, as the package was added by a user
+        if !self.maintainer_recorded {
+            self.maintainer = maintainer;
+            self.maintainer_recorded = true;
+            return;
+        }
+
+        let known = self.maintainer_change.as_ref().map(|c| &c.current).unwrap_or(&self.maintainer);
+        if *known == maintainer {
+            return;
+        }
+
+        let change = MaintainerChange {
+            previous: self.maintainer.clone(),
+            current: maintainer,
+            detected: Utc::now(),
+        };
+
+        warn!("{} of aur package {}", change.describe(), self.base);
+        self.maintainer_change = Some(change);
     }
 
     /// reads the version from the _local_ srcinfo, make sure the repo is
@@ -64,8 +165,10 @@ impl SourceImpl for AurSource {
             git::clone(&aur::get_repository(&self.base), folder, None).await?;
         }
 
-        self.version = if let Some(version) = self.get_version_aur().await? {
-            version
+        let info = self.get_info_aur().await?;
+
+        self.version = if let Some(info) = &info {
+            info.version.clone()
         } else {
             // some packages do not have a package that has the same base (e.g.
             // `material-symbols-git`) and the aur rpc interface does not
@@ -78,6 +181,9 @@ impl SourceImpl for AurSource {
             self.get_version_srcinfo(folder).await?
         };
 
+        // the maintainer is recorded when the package is added
+        self.track_maintainer(info, folder).await;
+
         Ok(())
     }
 
@@ -93,6 +199,24 @@ impl SourceImpl for AurSource {
         self.version.clone()
     }
 
+    fn get_add_source(&self, _devel: bool) -> Option<PackageAddSource> {
+        Some(PackageAddSource::Aur { name: self.base.clone() })
+    }
+
+    fn get_maintainer_change(&self) -> Option<MaintainerChange> {
+        self.maintainer_change.clone()
+    }
+
+    fn get_maintainer_tracked(&self) -> Option<bool> {
+        Some(self.maintainer_tracked)
+    }
+
+    fn acknowledge_maintainer(&mut self) {
+        if let Some(change) = self.maintainer_change.take() {
+            self.maintainer = change.current;
+        }
+    }
+
     async fn update(&mut self, folder: &Path) -> anyhow::Result<()> {
         debug!("updating aur source for {}", self.base);
 
@@ -118,12 +242,14 @@ impl SourceImpl for AurSource {
             return self.initialize(folder).await;
         }
 
-        if let Some(version) = self.get_version_aur().await? {
+        let info = self.get_info_aur().await?;
+
+        if let Some(info) = &info {
             // only update if version has changed
-            if version != self.version {
+            if info.version != self.version {
                 git::pull(folder).await?;
 
-                self.version = version;
+                self.version = info.version.clone();
             }
         } else {
             // for packages where the aur version lookup does not work,
@@ -133,6 +259,8 @@ impl SourceImpl for AurSource {
             self.version = self.get_version_srcinfo(folder).await?;
         }
 
+        self.track_maintainer(info, folder).await;
+
         Ok(())
     }
 }
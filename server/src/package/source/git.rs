@@ -3,6 +3,7 @@ use crate::package::source::{Source, SourceImpl};
 use async_trait::async_trait;
 use log::debug;
 use serde::{Deserialize, Serialize};
+use serene_data::package::PackageAddSource;
 use std::path::Path;
 
 /// this is a source which pulls the build files from a custom git repository
@@ -10,15 +11,19 @@ use std::path::Path;
 pub struct GitSource {
     repository: String,
     last_commit: String,
+    /// branch which is checked out, unknown for sources which were not updated
+    /// since it is stored or whose head is detached
+    #[serde(default)]
+    branch: Option<String>,
 }
 
 impl GitSource {
     pub fn new(repository: &str) -> Self {
-        Self { repository: repository.to_owned(), last_commit: "".to_owned() }
+        Self { repository: repository.to_owned(), last_commit: "".to_owned(), branch: None }
     }
 
     pub fn migrated(repository: String, last_commit: String) -> Self {
-        Self { repository, last_commit }
+        Self { repository, last_commit, branch: None }
     }
 }
 
@@ -30,6 +35,7 @@ impl SourceImpl for GitSource {
 
         git::clone(&self.repository, folder, None).await?;
         self.last_commit = git::find_local_commit(folder).await?;
+        self.branch = git::find_local_branch(folder).await?;
 
         Ok(())
     }
@@ -46,12 +52,26 @@ impl SourceImpl for GitSource {
         self.last_commit.clone()
     }
 
+    async fn get_branch(&self, folder: &Path) -> anyhow::Result<Option<String>> {
+        match &self.branch {
+            // older versions stored the head of detached checkouts as branch
+            Some(branch) if branch != "HEAD" => Ok(Some(branch.clone())),
+            // sources stored before the branch was recorded still have it checked out
+            _ => git::find_local_branch(folder).await,
+        }
+    }
+
+    fn get_add_source(&self, devel: bool) -> Option<PackageAddSource> {
+        Some(PackageAddSource::Git { url: self.repository.clone(), devel })
+    }
+
     async fn update(&mut self, folder: &Path) -> anyhow::Result<()> {
         debug!("updating git source for {}", self.repository);
 
         // pull repo
         git::pull(folder).await?;
         self.last_commit = git::find_local_commit(folder).await?;
+        self.branch = git::find_local_branch(folder).await?;
 
         Ok(())
     }
@@ -11,13 +11,14 @@ use anyhow::Context;
 use async_trait::async_trait;
 use dyn_clone::{clone_trait_object, DynClone};
 use serde::{Deserialize, Serialize};
+use serene_data::package::{MaintainerChange, PackageAddSource};
 use std::collections::HashMap;
 use std::path::Path;
 use std::str::FromStr;
 use tokio::fs;
 
 const SRCINFO: &str = ".SRCINFO";
-const PKGBUILD: &str = "PKGBUILD";
+pub(crate) const PKGBUILD: &str = "PKGBUILD";
 
 // Source types:
 // - cli source
@@ -44,6 +45,32 @@ pub trait SourceImpl: Sync + Send + DynClone {
     /// returns the current state of the source as a string
     fn get_state(&self) -> String;
 
+    /// returns the branch of the upstream repository the source follows, if it
+    /// has one
+    async fn get_branch(&self, _folder: &Path) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// returns how the source can be added again, if it can be added by users
+    fn get_add_source(&self, _devel: bool) -> Option<PackageAddSource> {
+        None
+    }
+
+    /// returns the change of the upstream maintainer which was not
+    /// acknowledged yet, if the source knows its maintainer
+    fn get_maintainer_change(&self) -> Option<MaintainerChange> {
+        None
+    }
+
+    /// returns whether changes of the upstream maintainer are currently
+    /// detected, none if the source has no maintainer
+    fn get_maintainer_tracked(&self) -> Option<bool> {
+        None
+    }
+
+    /// acknowledges the current upstream maintainer
+    fn acknowledge_maintainer(&mut self) {}
+
     /// update the source files to the newest version
     async fn update(&mut self, folder: &Path) -> anyhow::Result<()>;
 
@@ -179,6 +206,31 @@ impl Source {
         string
     }
 
+    /// get the branch of the upstream repository the source follows
+    pub async fn get_branch(&self, folder: &Path) -> anyhow::Result<Option<String>> {
+        self.inner.get_branch(folder).await
+    }
+
+    /// get how the source can be added again, if it can be added by users
+    pub fn get_add_source(&self) -> Option<PackageAddSource> {
+        self.inner.get_add_source(self.devel)
+    }
+
+    /// get the unacknowledged change of the upstream maintainer
+    pub fn get_maintainer_change(&self) -> Option<MaintainerChange> {
+        self.inner.get_maintainer_change()
+    }
+
+    /// get whether changes of the upstream maintainer are currently detected
+    pub fn get_maintainer_tracked(&self) -> Option<bool> {
+        self.inner.get_maintainer_tracked()
+    }
+
+    /// acknowledge the current upstream maintainer
+    pub fn acknowledge_maintainer(&mut self) {
+        self.inner.acknowledge_maintainer()
+    }
+
     /// get the srcinfo of the source
     pub async fn get_srcinfo(&self, folder: &Path) -> anyhow::Result<SrcinfoWrapper> {
         let srcinfo = if self.srcinfo_override {
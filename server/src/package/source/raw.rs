@@ -3,6 +3,7 @@ use crate::package::srcinfo::SrcinfoWrapper;
 use crate::runner::archive::InputArchive;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use serene_data::package::PackageAddSource;
 use serene_data::secret;
 use std::path::Path;
 
@@ -38,6 +39,10 @@ impl SourceImpl for RawSource {
         secret::hash(&self.pkgbuild)
     }
 
+    fn get_add_source(&self, devel: bool) -> Option<PackageAddSource> {
+        Some(PackageAddSource::Raw { pkgbuild: self.pkgbuild.clone(), devel })
+    }
+
     async fn update(&mut self, _folder: &Path) -> anyhow::Result<()> {
         Ok(())
     }
@@ -99,7 +99,7 @@ impl SrcinfoGenerator {
         let container = self.runner.prepare_srcinfo_container(true).await?;
 
         self.runner.upload_inputs(&container, input).await?;
-        let (status, logs) = self.runner.run(&container, None).await?;
+        let (status, logs) = self.runner.run(&container, None, None).await?;
 
         debug!("srcinfo generation finished with status {}", status.success);
 
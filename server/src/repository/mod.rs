@@ -53,6 +53,25 @@ pub async fn remove_orphan_signature() {
     }
 }
 
+/// returns the size of all files in the repository in bytes
+pub async fn size() -> anyhow::Result<u64> {
+    let Ok(mut dir) = fs::read_dir(REPO_DIR).await else {
+        // repository directory does not yet exist
+        return Ok(0);
+    };
+
+    let mut size = 0;
+    while let Some(entry) = dir.next_entry().await.context("failed to read repository")? {
+        let metadata = entry.metadata().await.context("failed to read repository file")?;
+
+        if metadata.is_file() {
+            size += metadata.len();
+        }
+    }
+
+    Ok(size)
+}
+
 /// returns the webservice which exposes the repository
 pub fn webservice() -> Files {
     Files::new(&CONFIG.architecture, REPO_DIR).show_files_listing()
@@ -1,10 +1,11 @@
 use crate::build::BuildSummary;
+use crate::build::schedule::BuildMeta;
 use crate::database::Database;
 use crate::package::Package;
 use crate::resolve::AurResolver;
 use crate::web::broadcast::Broadcast;
 use log::debug;
-use serene_data::build::{BuildProgress, BuildReason, BuildState};
+use serene_data::build::{BuildProgress, BuildState};
 use std::collections::HashSet;
 use std::sync::Arc;
 
@@ -34,19 +35,19 @@ impl<'a> BuildResolver<'a> {
     pub async fn add_and_resolve(
         &mut self,
         packages: Vec<Package>,
-        reason: BuildReason,
+        meta: BuildMeta,
     ) -> anyhow::Result<Vec<(Package, BuildSummary, HashSet<String>)>> {
-        self.add(packages, reason).await?;
+        self.add(packages, meta).await?;
         self.resolve().await
     }
 
     /// add packages that will be built
-    pub async fn add(&mut self, packages: Vec<Package>, reason: BuildReason) -> anyhow::Result<()> {
+    pub async fn add(&mut self, packages: Vec<Package>, meta: BuildMeta) -> anyhow::Result<()> {
         for package in packages {
             debug!("adding package {} to resolver", &package.base);
 
             // create build
-            let summary = BuildSummary::start(&package, reason);
+            let summary = BuildSummary::start(&package, meta.clone());
             summary.save(self.db).await?;
 
             // add them
@@ -1,10 +1,11 @@
-use std::{fs, path::PathBuf};
+use std::{fs, path::PathBuf, time::Instant};
 
 use alpm::{Alpm, SigLevel};
 use anyhow::{Context, Result};
 use log::{debug, info};
 
 use crate::config::CONFIG;
+use crate::web::metrics::METRICS;
 
 /// databases that are used by stock pacman
 const STOCK_DATABASES: [&str; 3] = ["core", "extra", "multilib"];
@@ -74,10 +75,12 @@ fn initialize_alpm() -> Result<Alpm> {
 /// updates the sync databases of a libalmp reference
 fn synchronize_alpm(alpm: &mut Alpm) -> Result<()> {
     info!("updating sync databases");
+    let start = Instant::now();
 
     alpm.syncdbs_mut().update(false).context("failed to synchronize databases with libalpm")?;
 
     debug!("finished updating sync databases successfully");
+    METRICS.sync_refreshed(start.elapsed());
 
     Ok(())
 }
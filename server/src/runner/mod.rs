@@ -1,4 +1,5 @@
 pub mod archive;
+pub mod retention;
 pub mod stats;
 pub mod update;
 
@@ -8,17 +9,21 @@ use crate::runner::archive::{InputArchive, OutputArchive};
 use crate::web::broadcast::BroadcastInstance;
 use anyhow::Context;
 use bollard::container::{
-    Config, CreateContainerOptions, DownloadFromContainerOptions, ListContainersOptions,
-    LogsOptions, StartContainerOptions, UploadToContainerOptions, WaitContainerOptions,
+    Config, CreateContainerOptions, DownloadFromContainerOptions, KillContainerOptions,
+    ListContainersOptions, LogsOptions, StartContainerOptions, UploadToContainerOptions,
+    WaitContainerOptions,
 };
 use bollard::image::{CreateImageOptions, PruneImagesOptions};
+use bollard::models::HostConfig;
 use bollard::{API_DEFAULT_VERSION, Docker};
 use chrono::{DateTime, Utc};
 use futures_util::{AsyncRead, StreamExt};
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
+use serene_data::package::ResourceLimits;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use std::vec;
 use tokio_util::compat::TokioAsyncReadCompatExt;
 use tokio_util::io::StreamReader;
@@ -33,6 +38,8 @@ const RUNNER_IMAGE_SRCINFO_ENTRY: &str = "./srcinfo.sh";
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunStatus {
     pub success: bool,
+    /// the container was killed because it ran for too long
+    pub timed_out: bool,
 
     pub started: DateTime<Utc>,
     pub ended: DateTime<Utc>,
@@ -76,6 +83,7 @@ impl Runner {
         &self,
         container: &ContainerId,
         broadcast_target: Option<String>,
+        timeout: Option<Duration>,
     ) -> anyhow::Result<(RunStatus, String)> {
         let start = Utc::now();
 
@@ -113,11 +121,24 @@ impl Runner {
         });
 
         // wait for container to exit
-        let result = self
+        let wait = self
             .docker
             .wait_container(container, None::<WaitContainerOptions<String>>)
-            .collect::<Vec<_>>()
-            .await;
+            .collect::<Vec<_>>();
+
+        let (result, timed_out) = if let Some(timeout) = timeout {
+            match tokio::time::timeout(timeout, wait).await {
+                Ok(result) => (result, false),
+                Err(_) => {
+                    warn!("killing container {container}, it exceeded its timeout");
+                    self.kill(container).await?;
+
+                    (vec![], true)
+                }
+            }
+        } else {
+            (wait.await, false)
+        };
 
         let end = Utc::now();
 
@@ -127,6 +148,7 @@ impl Runner {
         Ok((
             RunStatus {
                 success: result.first().and_then(|r| r.as_ref().ok()).is_some(),
+                timed_out,
                 started: start,
                 ended: end,
             },
@@ -171,8 +193,13 @@ impl Runner {
 
     /// prepares a container for srcinfo generation
     pub async fn prepare_srcinfo_container(&self, clean: bool) -> anyhow::Result<ContainerId> {
-        self.prepare_container(&CONFIG.container_srcinfo_name, RUNNER_IMAGE_SRCINFO_ENTRY, clean)
-            .await
+        self.prepare_container(
+            &CONFIG.container_srcinfo_name,
+            RUNNER_IMAGE_SRCINFO_ENTRY,
+            &ResourceLimits::default(),
+            clean,
+        )
+        .await
     }
 
     /// prepares a container for a package build
@@ -181,7 +208,13 @@ impl Runner {
         package: &Package,
         clean: bool,
     ) -> anyhow::Result<ContainerId> {
-        self.prepare_container(&container_name(package), RUNNER_IMAGE_BULID_ENTRY, clean).await
+        self.prepare_container(
+            &container_name(package),
+            RUNNER_IMAGE_BULID_ENTRY,
+            &package.get_limits(),
+            clean,
+        )
+        .await
     }
 
     /// prepares a container based on the runner image
@@ -190,6 +223,7 @@ impl Runner {
         &self,
         name: &str,
         entrypoint: &str,
+        limits: &ResourceLimits,
         clean: bool,
     ) -> anyhow::Result<ContainerId> {
         // try recycle old container
@@ -200,7 +234,9 @@ impl Runner {
                     break 'check;
                 }
 
-                let Some(config) = self.docker.inspect_container(&id, None).await?.config else {
+                let inspect = self.docker.inspect_container(&id, None).await?;
+
+                let Some(config) = inspect.config else {
                     warn!("updating container {name}, because container is inaccessible");
                     break 'check;
                 };
@@ -226,13 +262,23 @@ impl Runner {
                     break 'check;
                 }
 
+                let current = inspect.host_config.unwrap_or_default();
+                let target = host_config(limits);
+                if !same_limit(current.memory, target.memory)
+                    || !same_limit(current.nano_cpus, target.nano_cpus)
+                    || !same_limit(current.pids_limit, target.pids_limit)
+                {
+                    info!("updating container {name}, because resource limits have changed");
+                    break 'check;
+                }
+
                 return Ok(id);
             }
 
             self.clean(&id).await.context("could not remove container whilst update")?;
         }
 
-        self.create_container(name, entrypoint).await
+        self.create_container(name, entrypoint, limits).await
     }
 
     /// finds an already created container under a name
@@ -255,8 +301,13 @@ impl Runner {
         }
     }
 
-    /// creates a new container given name and entry point
-    async fn create_container(&self, name: &str, entrypoint: &str) -> anyhow::Result<ContainerId> {
+    /// creates a new container given name, entry point and resource limits
+    async fn create_container(
+        &self,
+        name: &str,
+        entrypoint: &str,
+        limits: &ResourceLimits,
+    ) -> anyhow::Result<ContainerId> {
         let mut envs = HashMap::new();
         if let Some(packager) = &CONFIG.packager {
             envs.insert("PACKAGER", packager);
@@ -266,6 +317,7 @@ impl Runner {
             image: Some(target_docker_image()),
             entrypoint: Some(vec![entrypoint.to_owned()]),
             env: Some(envs.into_iter().map(|(key, value)| format!("{key}={value}")).collect()),
+            host_config: Some(host_config(limits)),
             ..Default::default()
         };
 
@@ -325,6 +377,35 @@ impl Runner {
         Ok(())
     }
 
+    /// kills the container for a given package if it is currently running
+    pub async fn kill_build_container(&self, package: &Package) -> anyhow::Result<()> {
+        if let Some(container) = self.find_container(&container_name(package)).await? {
+            self.kill(&container).await?
+        }
+
+        Ok(())
+    }
+
+    /// kills the container, if it is running
+    pub async fn kill(&self, container: &ContainerId) -> anyhow::Result<()> {
+        let running = self
+            .docker
+            .inspect_container(container, None)
+            .await?
+            .state
+            .and_then(|s| s.running)
+            .unwrap_or_default();
+
+        if running {
+            self.docker
+                .kill_container(container, None::<KillContainerOptions<String>>)
+                .await
+                .context("failed to kill container")?;
+        }
+
+        Ok(())
+    }
+
     /// cleans the container, i.e. removes it
     pub async fn clean(&self, container: &ContainerId) -> anyhow::Result<()> {
         self.docker
@@ -339,6 +420,25 @@ fn container_name(package: &Package) -> String {
     format!("{}{}", CONFIG.container_prefix, &package.base)
 }
 
+/// creates the host config applying the resource limits to a container
+fn host_config(limits: &ResourceLimits) -> HostConfig {
+    let memory = limits.memory.map(|m| (m * 1024 * 1024) as i64);
+
+    HostConfig {
+        memory,
+        // same as the memory, so the container can't exceed its limit by swapping
+        memory_swap: memory,
+        nano_cpus: limits.cpus.map(|c| (c as f64 * 1e9) as i64),
+        pids_limit: limits.pids.map(|p| p as i64),
+        ..Default::default()
+    }
+}
+
+/// compares two limits of a host config, where zero or less means unlimited
+fn same_limit(a: Option<i64>, b: Option<i64>) -> bool {
+    a.filter(|a| *a > 0) == b.filter(|b| *b > 0)
+}
+
 /// get the docker image name that should be used
 fn target_docker_image() -> String {
     CONFIG.runner_image.replace("{version}", &INFO.version)
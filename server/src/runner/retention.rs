@@ -0,0 +1,57 @@
+use crate::config::CONFIG;
+use crate::database::Database;
+use crate::database::retention::purge;
+use anyhow::Context;
+use chrono::Utc;
+use log::{debug, error};
+use std::str::FromStr;
+
+/// Schedules the purging of old builds and their logs
+pub struct RetentionScheduler {
+    db: Database,
+}
+
+impl RetentionScheduler {
+    /// creates a new retention scheduler
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// starts the scheduler, if any retention rules are configured
+    pub async fn start(&self) -> anyhow::Result<()> {
+        if CONFIG.retention_max_age.is_none() && CONFIG.retention_max_builds.is_none() {
+            debug!("no retention rules configured, not starting retention scheduler");
+            return Ok(());
+        }
+
+        let db = self.db.clone();
+        let cron = cron::Schedule::from_str(&CONFIG.schedule_retention)
+            .context("failed to parse retention cron string")?;
+
+        tokio::task::spawn(async move {
+            loop {
+                let Some(time) = cron.upcoming(Utc).next() else {
+                    error!("retention cron string has no time, aborting retention scheduler");
+                    break;
+                };
+
+                debug!("blocking until next retention schedule {time:#}");
+
+                if let Ok(duration) = (time - Utc::now()).to_std() {
+                    tokio::time::sleep(duration).await;
+
+                    if let Err(e) = purge(&db).await {
+                        error!("failed to purge old builds: {e:#}");
+                    }
+                } else {
+                    error!("next retention schedule out of range, aborting retention scheduler");
+                    break;
+                }
+            }
+
+            debug!("retention scheduler finished");
+        });
+
+        Ok(())
+    }
+}
@@ -20,6 +20,9 @@ pub struct CgroupStats {
     /// total io bytes written
     #[serde(rename(deserialize = "io_total_bytes_written"))]
     pub io_tbw: Option<usize>,
+    /// amount of processes killed because the memory limit was reached
+    #[serde(rename(deserialize = "oom_kills"))]
+    pub oom_kills: Option<usize>,
 }
 
 impl Sub for CgroupStats {
@@ -32,6 +35,7 @@ impl Sub for CgroupStats {
             cpu_system: self.cpu_system.zip(rhs.cpu_system).map(|(lhs, rhs)| lhs - rhs),
             io_tbr: self.io_tbr.zip(rhs.io_tbr).map(|(lhs, rhs)| lhs - rhs),
             io_tbw: self.io_tbw.zip(rhs.io_tbw).map(|(lhs, rhs)| lhs - rhs),
+            oom_kills: self.oom_kills.zip(rhs.oom_kills).map(|(lhs, rhs)| lhs - rhs),
         }
     }
 }
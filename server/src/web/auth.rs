@@ -1,49 +1,114 @@
 use crate::config::CONFIG;
+use crate::database::Database;
+use crate::database::audit::AuditEntry;
+use crate::package::Package;
+use crate::web::InternalError;
 use actix_web::dev::Payload;
 use actix_web::error::{
     ErrorBadRequest, ErrorForbidden, ErrorInternalServerError, ErrorServiceUnavailable,
     ErrorUnauthorized,
 };
 use actix_web::http::header::AUTHORIZATION;
-use actix_web::web::Query;
+use actix_web::web::{Data, Query};
 use actix_web::{FromRequest, HttpRequest};
+use chrono::{DateTime, Utc};
+use log::warn;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use serde::Serialize;
 use serene_data::secret;
+use serene_data::secret::{Scope, SecretInfo};
 use std::collections::HashMap;
 use std::future::Future;
+use std::marker::PhantomData;
 use std::pin::Pin;
+use std::str::FromStr;
 
-const AUTHORIZED_PATH: &str = "authorized_secrets";
+/// option of an authorized secret restricting its scopes
+const SCOPES_OPTION: &str = "scopes=";
+/// option of an authorized secret restricting the packages it may access
+const PACKAGES_OPTION: &str = "packages=";
+/// cookie the web ui stores the secret in
+pub const SECRET_COOKIE: &str = "serene_secret";
+/// length of the random nonce webhook secrets of a package are derived with
+const WEBHOOK_NONCE_LENGTH: usize = 32;
+
+/// scope a write extractor requires the secret to have
+pub trait RequiredScope: 'static {
+    const SCOPE: Scope;
+}
+
+/// requires the secret to be allowed to trigger builds
+pub struct BuildScope;
+impl RequiredScope for BuildScope {
+    const SCOPE: Scope = Scope::Build;
+}
+
+/// requires the secret to be allowed to manage packages
+pub struct ManageScope;
+impl RequiredScope for ManageScope {
+    const SCOPE: Scope = Scope::Manage;
+}
+
+/// requires the secret to be allowed to maintain the server
+pub struct AdminScope;
+impl RequiredScope for AdminScope {
+    const SCOPE: Scope = Scope::Admin;
+}
 
 /// this extractor makes sure that users are authorized when making special
-/// requests
-pub struct AuthWrite(String);
-impl FromRequest for AuthWrite {
+/// requests, their secret must have the given scope and be allowed to access
+/// the package in the path if there is one
+pub struct AuthWrite<S: RequiredScope>(String, AuthorizedSecret, String, PhantomData<S>);
+impl<S: RequiredScope> FromRequest for AuthWrite<S> {
     type Error = actix_web::Error;
     type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-        let secret = match req.headers().get(AUTHORIZATION) {
-            Some(value) => Ok(value.to_str().unwrap_or("").to_string()),
-            None => Err(ErrorUnauthorized("no secret provided")),
-        };
+        let secret = header_secret(req);
+        let package = package_param(req);
+        let db = database(req);
+        let endpoint = format!("{} {}", req.method(), req.path());
 
         Box::pin(async move {
             let secret = secret?;
-            if secret_authorized(&secret).await? {
-                Ok(Self(secret))
-            } else {
-                Err(ErrorForbidden("invalid secret"))
-            }
+            let authorized = authorize(&db?, &secret, S::SCOPE, package.as_deref()).await?;
+
+            Ok(Self(secret, authorized, endpoint, PhantomData))
         })
     }
 }
 
-impl AuthWrite {
+impl<S: RequiredScope> AuthWrite<S> {
     pub fn get_secret(&self) -> &String {
         &self.0
     }
+
+    pub fn get_authorized(&self) -> &AuthorizedSecret {
+        &self.1
+    }
+
+    /// records the action performed with this request in the audit log
+    pub async fn audit(&self, db: &Database) {
+        self.record(db, None).await
+    }
+
+    /// records the action performed with this request in the audit log,
+    /// together with its body, which should not contain private values
+    pub async fn audit_with(&self, db: &Database, body: &impl Serialize) {
+        self.record(db, serde_json::to_string(body).ok()).await
+    }
+
+    async fn record(&self, db: &Database, body: Option<String>) {
+        let entry = AuditEntry::new(self.1.label(), self.2.clone(), body);
+
+        // the action was already performed, so we don't fail the request
+        if let Err(e) = entry.save(db).await {
+            warn!("failed to record {} in audit log: {e:#}", self.2);
+        }
+    }
 }
 
-pub struct AuthRead(Option<String>);
+pub struct AuthRead(Option<String>, Option<AuthorizedSecret>);
 impl FromRequest for AuthRead {
     type Error = actix_web::Error;
     type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
@@ -51,15 +116,25 @@ impl FromRequest for AuthRead {
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
         if CONFIG.allow_reads {
             // always allow
-            Box::pin(async { Ok(Self(None)) })
+            Box::pin(async { Ok(Self(None, None)) })
         } else {
             let req = req.clone();
+            let package = package_param(&req);
 
             Box::pin(async move {
-                let mut payload = Payload::None;
+                // the web ui authenticates with a cookie, which only allows reading
+                let secret = if req.headers().get(AUTHORIZATION).is_none()
+                    && let Some(cookie) = req.cookie(SECRET_COOKIE)
+                {
+                    cookie.value().to_string()
+                } else {
+                    header_secret(&req)?
+                };
+
+                let authorized =
+                    authorize(&database(&req)?, &secret, Scope::Read, package.as_deref()).await?;
 
-                // delegate processing to write auth
-                AuthWrite::from_request(&req.clone(), &mut payload).await.map(|a| Self(Some(a.0)))
+                Ok(Self(Some(secret), Some(authorized)))
             })
         }
     }
@@ -69,6 +144,48 @@ impl AuthRead {
     pub fn get_secret(&self) -> &Option<String> {
         &self.0
     }
+
+    /// returns the authorized secret, none if reads are allowed without one
+    pub fn get_authorized(&self) -> &Option<AuthorizedSecret> {
+        &self.1
+    }
+
+    /// returns whether the request may read the given package
+    pub fn allows_package(&self, package: &str) -> bool {
+        self.1.as_ref().is_none_or(|s| s.allows_package(package))
+    }
+}
+
+/// this extractor allows access with the metrics token if one is configured,
+/// and otherwise falls back to read authorization
+pub struct AuthMetrics;
+impl FromRequest for AuthMetrics {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        if let Some(token) = &CONFIG.metrics_token
+            && let Some(value) = req.headers().get(AUTHORIZATION).and_then(|v| v.to_str().ok())
+            // hashes are compared so the time taken does not leak the token
+            && secret::hash(value.strip_prefix("Bearer ").unwrap_or(value)) == secret::hash(token)
+        {
+            return Box::pin(async { Ok(Self) });
+        }
+
+        let req = req.clone();
+
+        Box::pin(async move {
+            let mut payload = Payload::None;
+
+            // delegate processing to read auth, metrics cover all packages
+            let auth = AuthRead::from_request(&req, &mut payload).await?;
+            if let Some(authorized) = auth.get_authorized() {
+                authorized.check_unrestricted()?;
+            }
+
+            Ok(Self)
+        })
+    }
 }
 
 pub struct AuthWebhook(String);
@@ -90,19 +207,17 @@ impl FromRequest for AuthWebhook {
             .get("name")
             .ok_or(ErrorBadRequest("no package name parameter found"))
             .cloned();
+        let db = database(req);
 
         Box::pin(async move {
             let webhook_secret = webhook_secret?;
-            let secrets = get_secrets().await?;
             let name = name?;
 
-            for authorized_secret in secrets.into_iter() {
-                if create_webhook_secret(&name, &authorized_secret)?.eq(&webhook_secret) {
-                    return Ok(Self(webhook_secret));
-                }
+            if webhook_secrets(&db?, &name).await?.contains(&webhook_secret) {
+                Ok(Self(webhook_secret))
+            } else {
+                Err(ErrorForbidden("no signing secret found"))
             }
-
-            Err(ErrorForbidden("no signing secret found"))
         })
     }
 }
@@ -113,35 +228,233 @@ impl AuthWebhook {
     }
 }
 
-/// get all authorized secrets
-async fn get_secrets() -> actix_web::Result<Vec<String>> {
-    let file = tokio::fs::read_to_string(AUTHORIZED_PATH)
+/// secret which is authorized to access the api
+#[derive(Clone)]
+pub struct AuthorizedSecret {
+    /// id of the secret in the database, zero if it was not saved yet
+    pub id: i64,
+    /// hash of the secret
+    pub hash: String,
+    /// comment of the secret, usually the user and host it belongs to
+    pub comment: String,
+    /// scopes the secret was granted
+    pub scopes: Vec<Scope>,
+    /// globs of the package bases the secret may access, empty if unrestricted
+    pub packages: Vec<String>,
+    /// when the secret was authorized
+    pub created: DateTime<Utc>,
+    /// when the secret was last used
+    pub used: Option<DateTime<Utc>>,
+}
+
+impl AuthorizedSecret {
+    pub fn new(hash: String, comment: String, scopes: Vec<Scope>, packages: Vec<String>) -> Self {
+        Self { id: 0, hash, comment, scopes, packages, created: Utc::now(), used: None }
+    }
+
+    /// parses a line of the authorized secrets file, which consists of the
+    /// hash, optional `scopes=` and `packages=` restrictions and a comment
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace().peekable();
+        let hash = words.next()?.to_string();
+
+        // secrets without restrictions have always had full access
+        let mut scopes = vec![Scope::Admin];
+        let mut packages = vec![];
+
+        while let Some(word) = words.peek() {
+            if let Some(list) = word.strip_prefix(SCOPES_OPTION) {
+                scopes = list
+                    .split(',')
+                    .filter_map(|s| match Scope::from_str(s) {
+                        Ok(scope) => Some(scope),
+                        Err(_) => {
+                            warn!("ignoring unknown scope '{s}' in authorized secrets");
+                            None
+                        }
+                    })
+                    .collect();
+            } else if let Some(list) = word.strip_prefix(PACKAGES_OPTION) {
+                packages = list.split(',').filter(|p| !p.is_empty()).map(str::to_string).collect();
+            } else {
+                // the rest of the line is the comment
+                break;
+            }
+
+            words.next();
+        }
+
+        let comment = words.collect::<Vec<_>>().join(" ");
+
+        Some(Self::new(hash, comment, scopes, packages))
+    }
+
+    /// returns the name of the secret to show to users, which is its comment
+    pub fn label(&self) -> String {
+        if self.comment.is_empty() { format!("secret #{}", self.id) } else { self.comment.clone() }
+    }
+
+    /// returns whether the secret was granted the given scope
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.iter().any(|s| s.grants(scope))
+    }
+
+    /// returns whether the secret may access the given package
+    pub fn allows_package(&self, package: &str) -> bool {
+        self.packages.is_empty() || self.packages.iter().any(|glob| glob_matches(glob, package))
+    }
+
+    /// makes sure the secret may access the given package
+    pub fn check_package(&self, package: &str) -> actix_web::Result<()> {
+        if self.allows_package(package) {
+            Ok(())
+        } else {
+            Err(ErrorForbidden(format!("secret is not allowed to access package {package}")))
+        }
+    }
+
+    /// makes sure the secret is not restricted to certain packages, used for
+    /// requests which affect packages that are not known beforehand
+    pub fn check_unrestricted(&self) -> actix_web::Result<()> {
+        if self.packages.is_empty() {
+            Ok(())
+        } else {
+            Err(ErrorForbidden("secret is restricted to certain packages"))
+        }
+    }
+
+    pub fn to_info(&self) -> SecretInfo {
+        SecretInfo {
+            id: self.id,
+            hash: self.hash.clone(),
+            comment: self.comment.clone(),
+            scopes: self.scopes.clone(),
+            packages: self.packages.clone(),
+            created: self.created,
+            used: self.used,
+        }
+    }
+}
+
+/// matches a package base against a glob, where `*` matches any characters
+fn glob_matches(glob: &str, package: &str) -> bool {
+    let Some((prefix, rest)) = glob.split_once('*') else {
+        return glob == package;
+    };
+
+    let Some(package) = package.strip_prefix(prefix) else {
+        return false;
+    };
+
+    // the wildcard can match any amount of characters
+    package
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([package.len()])
+        .any(|i| glob_matches(rest, &package[i..]))
+}
+
+/// returns the database from the app data of a request
+fn database(req: &HttpRequest) -> actix_web::Result<Database> {
+    req.app_data::<Data<Database>>()
+        .map(|db| db.get_ref().clone())
+        .ok_or_else(|| ErrorInternalServerError("database is not available"))
+}
+
+/// reads the secret from the authorization header of a request
+fn header_secret(req: &HttpRequest) -> actix_web::Result<String> {
+    match req.headers().get(AUTHORIZATION) {
+        Some(value) => Ok(value.to_str().unwrap_or("").to_string()),
+        None => Err(ErrorUnauthorized("no secret provided")),
+    }
+}
+
+/// returns the package base in the path of a request, if it has one
+fn package_param(req: &HttpRequest) -> Option<String> {
+    req.match_info().get("name").map(str::to_string)
+}
+
+/// finds the entry of a secret and makes sure it has the given scope and may
+/// access the given package
+async fn authorize(
+    db: &Database,
+    secret: &str,
+    scope: Scope,
+    package: Option<&str>,
+) -> actix_web::Result<AuthorizedSecret> {
+    let authorized = AuthorizedSecret::find(&secret::hash(secret), db)
         .await
-        .map_err(|_e| ErrorInternalServerError("failed to read authorized secrets"))?;
+        .internal()?
+        .ok_or_else(|| ErrorForbidden("invalid secret"))?;
 
-    let secrets = file
-        .trim()
-        .split('\n')
-        .filter_map(|s| s.split_whitespace().next().map(|str| str.to_string()))
-        .collect::<Vec<String>>();
-    Ok(secrets)
+    if !authorized.has_scope(scope) {
+        return Err(ErrorForbidden(format!("secret does not have the {scope} scope")));
+    }
+
+    if let Some(package) = package {
+        authorized.check_package(package)?;
+    }
+
+    authorized.mark_used(db).await.internal()?;
+
+    Ok(authorized)
 }
 
-/// checks whether a given secret is authorized
-async fn secret_authorized(secret: &str) -> Result<bool, actix_web::Error> {
-    let secrets = get_secrets().await?;
-    Ok(secrets.contains(&secret::hash(secret)))
+/// checks whether a given secret is authorized with the given scope
+pub async fn secret_authorized(
+    db: &Database,
+    secret: &str,
+    scope: Scope,
+) -> Result<bool, actix_web::Error> {
+    let authorized = AuthorizedSecret::find(&secret::hash(secret), db).await.internal()?;
+    Ok(authorized.is_some_and(|s| s.has_scope(scope)))
+}
+
+/// returns all webhook secrets which are currently valid for a package, none
+/// if the package is not added
+pub async fn webhook_secrets(db: &Database, package: &str) -> actix_web::Result<Vec<String>> {
+    let Some(package) = Package::find(package, db).await.internal()? else {
+        return Ok(vec![]);
+    };
+
+    let mut secrets = vec![];
+
+    for authorized in AuthorizedSecret::find_all(db).await.internal()? {
+        // the secret which issued the webhook secret must still be allowed to build
+        if authorized.has_scope(Scope::Build) && authorized.allows_package(&package.base) {
+            secrets.push(create_webhook_secret(&package, &authorized.hash)?);
+        }
+    }
+
+    Ok(secrets)
 }
 
 /// create a secret which can be used for webhooks for a given package
 pub fn create_webhook_secret(
-    package: &String,
+    package: &Package,
     authorized_secret: &String,
 ) -> actix_web::Result<String> {
     let server_secret = CONFIG
         .webhook_secret
         .clone()
         .ok_or(ErrorServiceUnavailable("webhooks aren't enabled on this server"))?;
-    let secret_str = format!("{authorized_secret}-{package}-{server_secret}");
+
+    let base = &package.base;
+    let secret_str = if let Some(nonce) = &package.webhook_nonce {
+        format!("{authorized_secret}-{base}-{nonce}-{server_secret}")
+    } else {
+        // packages without a nonce keep the secrets they had before
+        format!("{authorized_secret}-{base}-{server_secret}")
+    };
+
     Ok(secret::hash_url_safe(secret_str.as_str()))
 }
+
+/// generates a new random nonce for the webhook secrets of a package
+pub fn generate_webhook_nonce() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(WEBHOOK_NONCE_LENGTH)
+        .map(char::from)
+        .collect()
+}
@@ -1,3 +1,4 @@
+use crate::web::auth::AuthorizedSecret;
 use actix_web_lab::sse;
 use actix_web_lab::sse::{Data, Event, Sse};
 use actix_web_lab::util::InfallibleStream;
@@ -5,7 +6,7 @@ use futures::future::join_all;
 use log::{debug, error, trace};
 use serene_data::build::BuildState;
 use serene_data::package::BroadcastEvent;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
@@ -13,8 +14,48 @@ use tokio_stream::wrappers::ReceiverStream;
 
 pub type BroadcastInstance = Arc<Broadcast>;
 
+/// filter for subscriptions which receive events of all packages
+pub struct EventFilter {
+    /// only receive events of these packages, all packages if none
+    pub packages: Option<HashSet<String>>,
+    /// only receive these events, all events if none
+    pub events: Option<HashSet<String>>,
+    /// also receive build logs
+    pub logs: bool,
+    /// secret the events are read with, which may be restricted to packages
+    pub secret: Option<AuthorizedSecret>,
+}
+
+impl EventFilter {
+    /// does an event for a given package pass this filter
+    fn matches(&self, package: &str, event: &BroadcastEvent) -> bool {
+        // pings are always sent so we can detect stale connections
+        if matches!(event, BroadcastEvent::Ping) {
+            return true;
+        }
+
+        if !self.allows_package(package) {
+            return false;
+        }
+
+        if matches!(event, BroadcastEvent::Log(_)) && !self.logs {
+            return false;
+        }
+
+        self.packages.as_ref().map(|p| p.contains(package)).unwrap_or(true)
+            && self.events.as_ref().map(|e| e.contains(event.name())).unwrap_or(true)
+    }
+
+    /// may the subscriber receive events of a given package at all
+    fn allows_package(&self, package: &str) -> bool {
+        self.secret.as_ref().is_none_or(|s| s.allows_package(package))
+    }
+}
+
 pub struct Broadcast {
     subscriptions: Mutex<HashMap<String, Vec<tokio::sync::mpsc::Sender<sse::Event>>>>,
+    // global subscriptions receive events for every package which passes their filter
+    global: Mutex<Vec<(EventFilter, tokio::sync::mpsc::Sender<sse::Event>)>>,
     // cache contains build logs for packages which are currently building
     cache: Mutex<HashMap<String, (Vec<String>, BuildState)>>,
 }
@@ -23,6 +64,7 @@ impl Broadcast {
     pub fn new() -> Arc<Self> {
         let broadcast = Arc::new(Self {
             subscriptions: Mutex::new(HashMap::new()),
+            global: Mutex::new(vec![]),
             cache: Mutex::new(HashMap::new()),
         });
         Broadcast::spawn_ping(broadcast.clone());
@@ -65,6 +107,19 @@ impl Broadcast {
         .into_iter()
         .flatten()
         .collect::<HashMap<_, _>>();
+
+        let mut global = self.global.lock().await;
+        let alive = join_all(global.iter().map(|(_, recv)| async {
+            recv.send(
+                Self::create_event("", BroadcastEvent::Ping).expect("ping should be serializable"),
+            )
+            .await
+            .is_ok()
+        }))
+        .await;
+
+        let mut alive = alive.into_iter();
+        global.retain(|_| alive.next().unwrap_or_default());
     }
 
     /// subscribe to all package events
@@ -100,6 +155,44 @@ impl Broadcast {
         Ok(Sse::from_infallible_receiver(rx))
     }
 
+    /// subscribe to the events of all packages which pass a filter
+    pub async fn subscribe_all(
+        &self,
+        filter: EventFilter,
+    ) -> actix_web::Result<Sse<InfallibleStream<ReceiverStream<sse::Event>>>> {
+        // the state and logs of currently running builds are sent first, the
+        // cache stays locked until the receiver is registered so no event is
+        // lost in between, as changes and logs lock it before notifying
+        let cache = self.cache.lock().await;
+
+        let mut events = vec![];
+        for (package, (logs, state)) in cache.iter() {
+            let cached =
+                [BroadcastEvent::Change(state.clone()), BroadcastEvent::Log(logs.join(""))];
+
+            for event in cached.into_iter().filter(|e| filter.matches(package, e)) {
+                if let Some(event) = Self::create_event(package, event) {
+                    events.push(event);
+                } else {
+                    error!("failed serialize cached event to send to new receiver");
+                }
+            }
+        }
+
+        // the channel fits all cached events, so sending them never waits for
+        // the receiver while the cache is locked
+        let (tx, rx) = tokio::sync::mpsc::channel::<sse::Event>(events.len() + 10);
+        for event in events {
+            let _ = tx.try_send(event);
+        }
+
+        debug!("added new global receiver");
+        self.global.lock().await.push((filter, tx));
+        drop(cache);
+
+        Ok(Sse::from_infallible_receiver(rx))
+    }
+
     /// send a state change through the event source
     pub async fn change(&self, package: &str, state: BuildState) {
         let mut cache = self.cache.lock().await;
@@ -136,14 +229,29 @@ impl Broadcast {
         let subscriptions = self.subscriptions.lock().await;
         let receivers = subscriptions.get(&package).cloned().unwrap_or_default();
 
-        trace!("notifying package {package} with {} receivers", receivers.len());
+        drop(subscriptions);
+
+        let global = self
+            .global
+            .lock()
+            .await
+            .iter()
+            .filter(|(filter, _)| filter.matches(&package, &event))
+            .map(|(_, recv)| recv.clone())
+            .collect::<Vec<_>>();
+
+        trace!(
+            "notifying package {package} with {} receivers and {} global receivers",
+            receivers.len(),
+            global.len()
+        );
 
         let Some(event) = Self::create_event(&package, event) else {
             error!("failed to serialize event to send to event source");
             return;
         };
 
-        for receiver in receivers {
+        for receiver in receivers.into_iter().chain(global) {
             // we can ignore errors since the stale client gets removed in next cleanup
             // anyways
             receiver.send(event.clone()).await.ok();
@@ -1,6 +1,7 @@
 use crate::build::BuildSummary;
 use crate::package::Package;
 use serene_data::build::BuildInfo;
+use serene_data::manifest::{ManifestPackage, REDACTED_PREPARE};
 use serene_data::package::{PackageInfo, PackagePeek};
 
 impl Package {
@@ -13,9 +14,31 @@ impl Package {
             added: self.added,
             members: self.get_packages(),
             build: build.map(|b| b.as_info()),
+            maintainer_change: self.source.get_maintainer_change(),
         }
     }
 
+    /// describes the package for a manifest, packages which can't be added by
+    /// users are not part of it
+    pub fn to_manifest(&self) -> Option<ManifestPackage> {
+        Some(ManifestPackage {
+            base: self.base.clone(),
+            source: self.source.get_add_source()?,
+            devel: self.source.devel,
+            enabled: self.enabled,
+            clean: self.clean,
+            private: self.private,
+            schedule: self.schedule.clone(),
+            prepare: if self.private {
+                self.prepare.as_ref().map(|_| REDACTED_PREPARE.to_string())
+            } else {
+                self.prepare.clone()
+            },
+            flags: self.flags.clone(),
+            review: self.review,
+        })
+    }
+
     pub fn to_info(&self, build_count: u32) -> PackageInfo {
         PackageInfo {
             base: self.base.clone(),
@@ -36,6 +59,17 @@ impl Package {
             schedule_changed: self.schedule.is_some(),
             prepare_commands: if self.private { None } else { self.prepare.clone() },
             makepkg_flags: self.flags.clone(),
+            timeout: self.get_timeout(),
+            timeout_changed: self.timeout.is_some(),
+            limits: self.get_limits(),
+            limits_changed: !self.limits.is_empty(),
+            notify: self.get_notify(),
+            notify_changed: self.notify.is_some(),
+            webhook_fired: self.webhook_fired,
+            review: self.review,
+            review_pending: self.review_pending.is_some(),
+            maintainer_change: self.source.get_maintainer_change(),
+            maintainer_tracked: self.source.get_maintainer_tracked(),
             added: self.added,
         }
     }
@@ -54,6 +88,10 @@ impl BuildSummary {
             cpu_user: self.stats.as_ref().and_then(|s| s.cpu_user),
             io_tbr: self.stats.as_ref().and_then(|s| s.io_tbr),
             io_tbw: self.stats.as_ref().and_then(|s| s.io_tbw),
+            oom_kills: self.stats.as_ref().and_then(|s| s.oom_kills),
+            failure: self.failure.clone(),
+            commit: self.meta.as_ref().and_then(|m| m.commit.clone()),
+            findings: self.findings.clone(),
         }
     }
 }
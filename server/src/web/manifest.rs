@@ -0,0 +1,312 @@
+use crate::build::schedule::BuildMeta;
+use crate::database::Database;
+use crate::package::{self, Package};
+use crate::web::auth::{AuthWrite, ManageScope};
+use crate::web::broadcast::Broadcast;
+use crate::web::{
+    BuildSchedulerData, BuilderData, InternalError, SrcinfoGeneratorData, change_setting,
+    create_source,
+};
+use actix_web::error::ErrorBadRequest;
+use actix_web::web::{Data, Json};
+use actix_web::{Responder, get, post};
+use cron::Schedule;
+use serde::Serialize;
+use serene_data::build::BuildReason;
+use serene_data::manifest::{
+    Manifest, ManifestApplyRequest, ManifestChange, ManifestPackage, REDACTED_PREPARE,
+};
+use serene_data::package::{BroadcastEvent, MakepkgFlag, PackageAddSource, PackageSettingsRequest};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// manifest application which failed partway, for the audit log
+#[derive(Serialize)]
+struct FailedApply<'a> {
+    /// changes which were going to be made
+    changes: &'a [ManifestChange],
+    /// error the application failed with
+    error: String,
+}
+
+/// whether the package was added from the source in the manifest, the devel
+/// flag is compared separately
+fn same_source(package: &Package, wanted: &PackageAddSource) -> bool {
+    match (package.source.get_add_source(), wanted) {
+        (Some(PackageAddSource::Aur { name: a }), PackageAddSource::Aur { name: b }) => a == *b,
+        (Some(PackageAddSource::Git { url: a, .. }), PackageAddSource::Git { url: b, .. }) => {
+            a == *b
+        }
+        (
+            Some(PackageAddSource::Raw { pkgbuild: a, .. }),
+            PackageAddSource::Raw { pkgbuild: b, .. },
+        ) => a == *b,
+        // sources which cannot be added by users are never replaced
+        (None, _) => true,
+        _ => false,
+    }
+}
+
+/// describes an optional setting for the diff
+fn describe(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| "none".to_string())
+}
+
+/// describes makepkg flags for the diff
+fn describe_flags(flags: &[MakepkgFlag]) -> String {
+    if flags.is_empty() {
+        "none".to_string()
+    } else {
+        flags.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// settings which have to be changed for a package to match its manifest
+/// entry, together with the change shown to the user
+fn settings_diff(
+    package: &Package,
+    wanted: &ManifestPackage,
+) -> Vec<(PackageSettingsRequest, ManifestChange)> {
+    let mut diff = vec![];
+    let mut push = |setting: PackageSettingsRequest, name: &str, old: String, new: String| {
+        let change =
+            ManifestChange::Set { base: package.base.clone(), setting: name.to_string(), old, new };
+        diff.push((setting, change));
+    };
+
+    if package.source.devel != wanted.devel {
+        let (old, new) = (package.source.devel.to_string(), wanted.devel.to_string());
+        push(PackageSettingsRequest::Devel(wanted.devel), "devel", old, new);
+    }
+
+    if package.enabled != wanted.enabled {
+        let (old, new) = (package.enabled.to_string(), wanted.enabled.to_string());
+        push(PackageSettingsRequest::Enabled(wanted.enabled), "enabled", old, new);
+    }
+
+    if package.clean != wanted.clean {
+        let (old, new) = (package.clean.to_string(), wanted.clean.to_string());
+        push(PackageSettingsRequest::Clean(wanted.clean), "clean", old, new);
+    }
+
+    if package.private != wanted.private {
+        let (old, new) = (package.private.to_string(), wanted.private.to_string());
+        push(PackageSettingsRequest::Private(wanted.private), "private", old, new);
+    }
+
+    if package.schedule != wanted.schedule {
+        let (old, new) = (describe(&package.schedule), describe(&wanted.schedule));
+        push(PackageSettingsRequest::Schedule(wanted.schedule.clone()), "schedule", old, new);
+    }
+
+    // prepare commands of private packages are not exported, so they are kept
+    if wanted.prepare.as_deref() != Some(REDACTED_PREPARE) && package.prepare != wanted.prepare {
+        let private = package.private || wanted.private;
+        let redact = |prepare: &Option<String>| {
+            if private && prepare.is_some() {
+                REDACTED_PREPARE.to_string()
+            } else {
+                describe(prepare)
+            }
+        };
+
+        let (old, new) = (redact(&package.prepare), redact(&wanted.prepare));
+        push(PackageSettingsRequest::Prepare(wanted.prepare.clone()), "prepare", old, new);
+    }
+
+    if package.flags != wanted.flags {
+        let (old, new) = (describe_flags(&package.flags), describe_flags(&wanted.flags));
+        push(PackageSettingsRequest::Flags(wanted.flags.clone()), "flags", old, new);
+    }
+
+    if package.review != wanted.review {
+        let (old, new) = (package.review.to_string(), wanted.review.to_string());
+        push(PackageSettingsRequest::Review(wanted.review), "review", old, new);
+    }
+
+    diff
+}
+
+#[get("/manifest")]
+pub async fn get_manifest(
+    auth: AuthWrite<ManageScope>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    // the manifest describes the whole server
+    auth.get_authorized().check_unrestricted()?;
+
+    let mut packages = Package::find_all(&db).await.internal()?;
+    packages.sort_by(|a, b| a.base.cmp(&b.base));
+
+    // dependencies are added again together with the packages which need them
+    packages.retain(|p| !p.dependency);
+
+    Ok(Json(Manifest { packages: packages.iter().filter_map(|p| p.to_manifest()).collect() }))
+}
+
+#[post("/manifest")]
+pub async fn apply_manifest(
+    auth: AuthWrite<ManageScope>,
+    body: Json<ManifestApplyRequest>,
+    db: Data<Database>,
+    srcinfo_generator: SrcinfoGeneratorData,
+    scheduler: BuildSchedulerData,
+    builder: BuilderData,
+    broadcast: Data<Broadcast>,
+) -> actix_web::Result<impl Responder> {
+    auth.get_authorized().check_unrestricted()?;
+
+    let request = body.into_inner();
+
+    let mut bases = HashSet::new();
+    for wanted in &request.manifest.packages {
+        if !bases.insert(wanted.base.as_str()) {
+            return Err(ErrorBadRequest(format!("package {} is listed twice", wanted.base)));
+        }
+
+        if let Some(schedule) = &wanted.schedule
+            && Schedule::from_str(schedule).is_err()
+        {
+            return Err(ErrorBadRequest(format!(
+                "cannot parse cron expression of {} (you probably forgot the seconds)",
+                wanted.base
+            )));
+        }
+    }
+
+    let current = Package::find_all(&db).await.internal()?;
+
+    // calculate the diff first, so it can be returned for dry runs
+    let mut changes = vec![];
+    for wanted in &request.manifest.packages {
+        let base = wanted.base.clone();
+
+        if let Some(package) = current.iter().find(|p| p.base == wanted.base) {
+            if !same_source(package, &wanted.source) {
+                changes.push(ManifestChange::Replace { base });
+            }
+
+            changes.extend(settings_diff(package, wanted).into_iter().map(|(_, change)| change));
+        } else {
+            changes.push(ManifestChange::Add { base });
+        }
+    }
+
+    // packages which can't be added by users (i.e. the cli) and dependencies
+    // are not part of the manifest, so they are never removed
+    let removed = current
+        .into_iter()
+        .filter(|p| request.prune && !bases.contains(p.base.as_str()))
+        .filter(|p| p.source.get_add_source().is_some() && !p.dependency)
+        .collect::<Vec<_>>();
+
+    changes.extend(removed.iter().map(|p| ManifestChange::Remove { base: p.base.clone() }));
+
+    if request.dry_run || changes.is_empty() {
+        return Ok(Json(changes));
+    }
+
+    let result: actix_web::Result<()> = async {
+        let mut added = vec![];
+        for wanted in &request.manifest.packages {
+            // the package could also have been added as a dependency of another one
+            let (mut package, new) = match Package::find(&wanted.base, &db).await.internal()? {
+                Some(package) if same_source(&package, &wanted.source) => (package, false),
+                existing => {
+                    let mut source = create_source(&wanted.source).await?;
+                    source.devel = wanted.devel;
+
+                    let packages =
+                        package::add_source(&db, &srcinfo_generator, source, existing.is_some())
+                            .await
+                            .internal()?
+                            .ok_or_else(|| {
+                                ErrorBadRequest(format!("package {} is already added", wanted.base))
+                            })?;
+
+                    let mut target = None;
+                    for package in packages {
+                        scheduler.lock().await.schedule(&package).await.internal()?;
+                        broadcast.notify(&package.base, BroadcastEvent::Add).await;
+
+                        if package.base == wanted.base {
+                            target = Some(package);
+                        } else {
+                            added.push(package);
+                        }
+                    }
+
+                    let package = target.ok_or_else(|| {
+                        ErrorBadRequest(format!(
+                            "source of {} provides a different package base",
+                            wanted.base
+                        ))
+                    })?;
+
+                    (package, true)
+                }
+            };
+
+            let diff = settings_diff(&package, wanted);
+            let changed = !diff.is_empty();
+            let (mut reschedule, mut source) = (false, false);
+
+            for (setting, _) in diff {
+                let (r, s) = change_setting(&mut package, setting)?;
+                reschedule |= r;
+                source |= s;
+            }
+
+            if reschedule {
+                if package.enabled {
+                    scheduler.lock().await.schedule(&package).await.internal()?;
+                } else {
+                    scheduler.lock().await.unschedule(&package).await.internal()?;
+                }
+            }
+
+            if source {
+                package.update(&srcinfo_generator).await.internal()?;
+                package.change_sources(&db).await.internal()?;
+            }
+
+            package.change_settings(&db).await.internal()?;
+
+            if changed {
+                broadcast.notify(&package.base, BroadcastEvent::Settings).await;
+            }
+
+            if new {
+                added.push(package);
+            }
+        }
+
+        for package in &removed {
+            builder.run_remove(package).await.internal()?;
+            broadcast.notify(&package.base, BroadcastEvent::Remove).await;
+        }
+
+        if !added.is_empty() {
+            scheduler
+                .lock()
+                .await
+                .run(added, BuildMeta::new(BuildReason::Initial, true, true, false))
+                .await
+                .internal()?;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    // the changes made before a failure have to be recorded as well
+    match &result {
+        Ok(()) => auth.audit_with(&db, &changes).await,
+        Err(e) => {
+            auth.audit_with(&db, &FailedApply { changes: &changes, error: e.to_string() }).await
+        }
+    }
+
+    result?;
+    Ok(Json(changes))
+}
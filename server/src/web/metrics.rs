@@ -0,0 +1,259 @@
+use crate::build::BuildSummary;
+use crate::config::CONFIG;
+use crate::database::Database;
+use crate::package::Package;
+use crate::repository;
+use crate::web::auth::AuthMetrics;
+use crate::web::{BuilderData, InternalError};
+use actix_web::web::Data;
+use actix_web::{HttpResponse, Responder, get};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use serene_data::build::BuildState;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+lazy_static! {
+    pub static ref METRICS: Metrics = Metrics::default();
+}
+
+/// upper bounds of the buckets of the build duration histogram in seconds
+const DURATION_BUCKETS: [f64; 10] =
+    [30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0, 3600.0, 7200.0, 14400.0];
+
+/// metrics which are collected while serene is running, everything else is
+/// read when the metrics are requested
+#[derive(Default)]
+pub struct Metrics {
+    /// requests made to the aur rpc
+    aur_requests: AtomicU64,
+    /// requests to the aur rpc which failed
+    aur_errors: AtomicU64,
+    /// time of the last refresh of the sync databases and how long it took
+    sync_refresh: Mutex<Option<(DateTime<Utc>, Duration)>>,
+    /// totals of the builds finished since startup
+    builds: Mutex<BuildMetrics>,
+}
+
+#[derive(Default)]
+struct BuildMetrics {
+    /// amount of builds per duration bucket, not cumulative
+    buckets: [u64; DURATION_BUCKETS.len()],
+    /// amount of finished builds
+    count: u64,
+    /// total duration of the finished builds in seconds
+    duration: f64,
+
+    /// total cpu time spent in user mode in microseconds
+    cpu_user: u64,
+    /// total cpu time spent in system mode in microseconds
+    cpu_system: u64,
+    /// total io bytes read
+    io_read: u64,
+    /// total io bytes written
+    io_written: u64,
+    /// total processes killed because the memory limit was reached
+    oom_kills: u64,
+}
+
+impl Metrics {
+    /// records a request to the aur rpc
+    pub fn aur_request(&self, success: bool) {
+        self.aur_requests.fetch_add(1, Ordering::Relaxed);
+
+        if !success {
+            self.aur_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// records a refresh of the sync databases
+    pub fn sync_refreshed(&self, duration: Duration) {
+        *self.sync_refresh.lock().expect("metrics lock poisoned") = Some((Utc::now(), duration));
+    }
+
+    /// records a finished build
+    pub fn build_finished(&self, summary: &BuildSummary) {
+        let mut builds = self.builds.lock().expect("metrics lock poisoned");
+
+        if let Some(ended) = summary.ended {
+            let duration = (ended - summary.started).num_milliseconds() as f64 / 1000.0;
+
+            if let Some(bucket) = DURATION_BUCKETS.iter().position(|b| duration <= *b) {
+                builds.buckets[bucket] += 1;
+            }
+
+            builds.count += 1;
+            builds.duration += duration;
+        }
+
+        if let Some(stats) = &summary.stats {
+            builds.cpu_user += stats.cpu_user.unwrap_or_default() as u64;
+            builds.cpu_system += stats.cpu_system.unwrap_or_default() as u64;
+            builds.io_read += stats.io_tbr.unwrap_or_default() as u64;
+            builds.io_written += stats.io_tbw.unwrap_or_default() as u64;
+            builds.oom_kills += stats.oom_kills.unwrap_or_default() as u64;
+        }
+    }
+}
+
+/// returns the name of a build state as used in the labels
+fn state_name(state: &BuildState) -> &'static str {
+    match state {
+        BuildState::Pending => "pending",
+        BuildState::Cancelled(_) => "cancelled",
+        BuildState::Running(_) => "running",
+        BuildState::Success => "success",
+        BuildState::Failure => "failure",
+        BuildState::Fatal(_, _) => "fatal",
+        BuildState::Stopped(_) => "stopped",
+        BuildState::Review => "review",
+    }
+}
+
+/// writes the help and type lines of a metric
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+#[get("/metrics")]
+pub async fn metrics(
+    _: AuthMetrics,
+    db: Data<Database>,
+    builder: BuilderData,
+) -> actix_web::Result<impl Responder> {
+    let mut out = String::new();
+
+    // packages by the state of their latest build
+    let mut states = BTreeMap::<&str, usize>::new();
+    let mut memory = vec![];
+
+    for package in Package::find_all(&db).await.internal()? {
+        let build = BuildSummary::find_latest_for_package(&package.base, &db).await.internal()?;
+
+        *states
+            .entry(build.as_ref().map(|b| state_name(&b.state)).unwrap_or("none"))
+            .or_default() += 1;
+
+        if let Some(peak) = build.and_then(|b| b.stats).and_then(|s| s.mem_peak) {
+            memory.push((package.base, peak));
+        }
+    }
+
+    header(&mut out, "serene_packages", "gauge", "Packages by the state of their latest build.");
+    for (state, count) in states {
+        let _ = writeln!(out, "serene_packages{{state=\"{state}\"}} {count}");
+    }
+
+    header(
+        &mut out,
+        "serene_build_memory_peak_bytes",
+        "gauge",
+        "Peak memory usage of the latest build of a package.",
+    );
+    for (package, peak) in memory {
+        let _ = writeln!(out, "serene_build_memory_peak_bytes{{package=\"{package}\"}} {peak}");
+    }
+
+    // builds finished since startup
+    {
+        let builds = METRICS.builds.lock().expect("metrics lock poisoned");
+
+        header(
+            &mut out,
+            "serene_build_duration_seconds",
+            "histogram",
+            "Duration of the builds finished since startup.",
+        );
+        let mut cumulative = 0;
+        for (bound, count) in DURATION_BUCKETS.iter().zip(builds.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "serene_build_duration_seconds_bucket{{le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let _ =
+            writeln!(out, "serene_build_duration_seconds_bucket{{le=\"+Inf\"}} {}", builds.count);
+        let _ = writeln!(out, "serene_build_duration_seconds_sum {}", builds.duration);
+        let _ = writeln!(out, "serene_build_duration_seconds_count {}", builds.count);
+
+        for (name, help, value) in [
+            (
+                "serene_build_cpu_user_seconds_total",
+                "CPU time spent in user mode by builds.",
+                builds.cpu_user as f64 / 1_000_000.0,
+            ),
+            (
+                "serene_build_cpu_system_seconds_total",
+                "CPU time spent in system mode by builds.",
+                builds.cpu_system as f64 / 1_000_000.0,
+            ),
+            ("serene_build_io_read_bytes_total", "Bytes read by builds.", builds.io_read as f64),
+            (
+                "serene_build_io_written_bytes_total",
+                "Bytes written by builds.",
+                builds.io_written as f64,
+            ),
+            (
+                "serene_build_oom_kills_total",
+                "Processes killed in builds because they ran out of memory.",
+                builds.oom_kills as f64,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+    }
+
+    // build queue
+    header(&mut out, "serene_build_slots", "gauge", "Builds which can run concurrently.");
+    let _ = writeln!(out, "serene_build_slots {}", CONFIG.concurrent_builds);
+    header(&mut out, "serene_builds_running", "gauge", "Builds currently occupying a build slot.");
+    let _ = writeln!(out, "serene_builds_running {}", builder.running());
+    header(&mut out, "serene_builds_waiting", "gauge", "Builds waiting for a free build slot.");
+    let _ = writeln!(out, "serene_builds_waiting {}", builder.queued().await.len());
+
+    // aur rpc
+    header(&mut out, "serene_aur_requests_total", "counter", "Requests made to the AUR RPC.");
+    let _ =
+        writeln!(out, "serene_aur_requests_total {}", METRICS.aur_requests.load(Ordering::Relaxed));
+    header(
+        &mut out,
+        "serene_aur_request_errors_total",
+        "counter",
+        "Failed requests to the AUR RPC.",
+    );
+    let _ = writeln!(
+        out,
+        "serene_aur_request_errors_total {}",
+        METRICS.aur_errors.load(Ordering::Relaxed)
+    );
+
+    // sync databases
+    if let Some((time, duration)) = *METRICS.sync_refresh.lock().expect("metrics lock poisoned") {
+        header(
+            &mut out,
+            "serene_sync_refresh_timestamp_seconds",
+            "gauge",
+            "Time of the last refresh of the sync databases.",
+        );
+        let _ = writeln!(out, "serene_sync_refresh_timestamp_seconds {}", time.timestamp());
+        header(
+            &mut out,
+            "serene_sync_refresh_duration_seconds",
+            "gauge",
+            "Duration of the last refresh of the sync databases.",
+        );
+        let _ = writeln!(out, "serene_sync_refresh_duration_seconds {}", duration.as_secs_f64());
+    }
+
+    // repository
+    header(&mut out, "serene_repository_size_bytes", "gauge", "Size of the repository on disk.");
+    let _ = writeln!(out, "serene_repository_size_bytes {}", repository::size().await.internal()?);
+
+    Ok(HttpResponse::Ok().content_type("text/plain; version=0.0.4; charset=utf-8").body(out))
+}
@@ -1,33 +1,52 @@
 use crate::build::schedule::{BuildMeta, BuildScheduler};
 use crate::build::{BuildSummary, Builder};
 use crate::config::{CONFIG, INFO};
+use crate::database::audit::AuditEntry;
 use crate::database::{self, Database};
 use crate::package;
+use crate::package::files::{self, BuildFiles};
 use crate::package::srcinfo::SrcinfoGenerator;
 use crate::package::{aur, source, Package};
 use crate::repository::crypto::{get_public_key_bytes, should_sign_packages};
 use crate::repository::PackageRepositoryInstance;
-use crate::web::auth::{AuthRead, AuthWrite};
-use crate::web::broadcast::Broadcast;
+use crate::web::auth::{
+    AdminScope, AuthRead, AuthWrite, AuthorizedSecret, BuildScope, ManageScope,
+};
+use crate::web::broadcast::{Broadcast, EventFilter};
 use actix_web::error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound};
+use actix_web::http::header::ACCEPT;
 use actix_web::web::{Data, Json, Path, Query, Redirect};
-use actix_web::{delete, get, post, Responder};
-use auth::{create_webhook_secret, AuthWebhook};
+use actix_web::{delete, get, post, HttpRequest, HttpResponse, Responder};
+use auth::{create_webhook_secret, generate_webhook_nonce, AuthWebhook};
 use chrono::DateTime;
 use cron::Schedule;
 use hyper::StatusCode;
 use serde::Deserialize;
 use serene_data::build::BuildReason;
 use serene_data::package::{
-    PackageAddRequest, PackageAddSource, PackageBuildRequest, PackageSettingsRequest,
+    BroadcastEvent, PackageAddRequest, PackageAddSource, PackageBuildRequest,
+    PackageSettingsRequest,
 };
+use serene_data::secret::{is_hash, SecretAddRequest};
 use serene_data::SereneInfo;
+use std::collections::HashSet;
 use std::str::FromStr;
 use tokio::sync::Mutex;
+use tokio_util::io::ReaderStream;
 
-mod auth;
+pub mod auth;
 pub mod broadcast;
 mod data;
+pub mod manifest;
+pub mod metrics;
+pub mod review;
+pub mod ui;
+pub mod webhook;
+
+/// placeholder for private values which are not stored
+const REDACTED: &str = "<redacted>";
+/// amount of audit log entries returned if no count is given
+const DEFAULT_AUDIT_COUNT: u32 = 50;
 
 type BuildSchedulerData = Data<Mutex<BuildScheduler>>;
 type BuilderData = Data<Builder>;
@@ -59,16 +78,9 @@ pub async fn info() -> actix_web::Result<impl Responder> {
     }))
 }
 
-#[post("/package/add")]
-pub async fn add(
-    _: AuthWrite,
-    body: Json<PackageAddRequest>,
-    db: Data<Database>,
-    srcinfo_generator: SrcinfoGeneratorData,
-    scheduler: BuildSchedulerData,
-) -> actix_web::Result<impl Responder> {
-    // get repo and devel tag
-    let source = match &body.0.source {
+/// creates the source for a package which is added, looking it up on the aur
+async fn create_source(source: &PackageAddSource) -> actix_web::Result<source::Source> {
+    let source = match source {
         PackageAddSource::Aur { name } => {
             let base = if !CONFIG.aur_resolve_adding {
                 // we only use the github mirror if add resolving is not enabled
@@ -94,6 +106,23 @@ pub async fn add(
         PackageAddSource::Raw { pkgbuild: src, devel } => source::raw::new(src, *devel),
     };
 
+    Ok(source)
+}
+
+#[post("/package/add")]
+pub async fn add(
+    auth: AuthWrite<ManageScope>,
+    body: Json<PackageAddRequest>,
+    db: Data<Database>,
+    srcinfo_generator: SrcinfoGeneratorData,
+    scheduler: BuildSchedulerData,
+    broadcast: Data<Broadcast>,
+) -> actix_web::Result<impl Responder> {
+    // the packages which will be added are not known yet
+    auth.get_authorized().check_unrestricted()?;
+
+    let source = create_source(&body.0.source).await?;
+
     // create package
     let packages = package::add_source(&db, &srcinfo_generator, source, body.replace)
         .await
@@ -104,6 +133,8 @@ pub async fn add(
     for package in &packages {
         let count = BuildSummary::count_for_package(&package.base, &db).await.internal()?;
         response.push(package.to_info(count));
+
+        broadcast.notify(&package.base, BroadcastEvent::Add).await;
     }
 
     {
@@ -122,16 +153,18 @@ pub async fn add(
         }
     }
 
+    auth.audit_with(&db, &body.0).await;
+
     Ok(Json(response))
 }
 
 #[get("/package/list")]
-pub async fn list(_: AuthRead, db: Data<Database>) -> actix_web::Result<impl Responder> {
+pub async fn list(auth: AuthRead, db: Data<Database>) -> actix_web::Result<impl Responder> {
     let package = Package::find_all(&db).await.internal()?;
 
     let mut peeks = vec![];
 
-    for p in package {
+    for p in package.into_iter().filter(|p| auth.allows_package(&p.base)) {
         // retrieve latest build
         let b = BuildSummary::find_latest_for_package(&p.base, &db).await.internal()?;
 
@@ -173,6 +206,57 @@ pub async fn pkgbuild(
     })?))
 }
 
+#[derive(Deserialize)]
+struct DiffQuery {
+    /// build to compare from, the currently built state if empty
+    from: Option<String>,
+    /// build to compare to, the current source checkout if empty
+    to: Option<String>,
+}
+
+/// returns the build files a build of a package was run with
+async fn get_build_files_for(
+    base: &str,
+    time: &str,
+    db: &Database,
+) -> actix_web::Result<BuildFiles> {
+    let build = get_build_for(base, time, db)
+        .await?
+        .ok_or_else(|| ErrorNotFound("package not found or no build at this time"))?;
+
+    build
+        .find_files(db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("build files of build {time} were not recorded")))
+}
+
+#[get("/package/{name}/diff")]
+pub async fn diff(
+    _: AuthRead,
+    package: Path<String>,
+    Query(query): Query<DiffQuery>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    let package = Package::find(&package, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
+
+    let to = match &query.to {
+        Some(time) => get_build_files_for(&package.base, time, &db).await?,
+        None => files::read(&package).await.internal()?,
+    };
+
+    let from = match &query.from {
+        Some(time) => get_build_files_for(&package.base, time, &db).await?,
+        None => files::built(&package)
+            .ok_or_else(|| ErrorNotFound("package was never built and has thus no built files"))?,
+    };
+
+    Ok(Json(files::diff(&from, &to)))
+}
+
 #[derive(Deserialize)]
 struct CountQuery {
     count: Option<u32>,
@@ -196,11 +280,13 @@ pub async fn get_all_builds(
 
 #[post("/build/all")]
 pub async fn build_all(
-    _: AuthWrite,
+    auth: AuthWrite<BuildScope>,
     db: Data<Database>,
     body: Json<PackageBuildRequest>,
     scheduler: BuildSchedulerData,
 ) -> actix_web::Result<impl Responder> {
+    auth.get_authorized().check_unrestricted()?;
+
     let packages = Package::find_all(&db)
         .await
         .internal()?
@@ -215,12 +301,14 @@ pub async fn build_all(
         .await
         .internal()?;
 
+    auth.audit_with(&db, &body.0).await;
+
     Ok(empty_response())
 }
 
 #[post("/build")]
 pub async fn build(
-    _: AuthWrite,
+    auth: AuthWrite<BuildScope>,
     db: Data<Database>,
     body: Json<PackageBuildRequest>,
     scheduler: BuildSchedulerData,
@@ -228,6 +316,8 @@ pub async fn build(
     let mut packages = vec![];
 
     for package in &body.packages {
+        auth.get_authorized().check_package(package)?;
+
         packages.push(
             Package::find(package, &db).await.internal()?.ok_or_else(|| {
                 ErrorNotFound(format!("package with base {package} is not added"))
@@ -246,9 +336,165 @@ pub async fn build(
         .await
         .internal()?;
 
+    auth.audit_with(&db, &body.0).await;
+
+    Ok(empty_response())
+}
+
+#[delete("/package/{name}/build")]
+pub async fn stop_build(
+    auth: AuthWrite<BuildScope>,
+    package: Path<String>,
+    db: Data<Database>,
+    scheduler: BuildSchedulerData,
+    builder: BuilderData,
+) -> actix_web::Result<impl Responder> {
+    let package = Package::find(&package, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
+
+    if !scheduler.lock().await.is_locked(&package).await {
+        return Err(ErrorBadRequest(format!(
+            "package with base {} is not currently being built",
+            &package.base
+        )));
+    }
+
+    builder.stop(&package).await.internal()?;
+    auth.audit(&db).await;
+
+    Ok(empty_response())
+}
+
+#[get("/queue")]
+pub async fn queue(
+    auth: AuthRead,
+    scheduler: BuildSchedulerData,
+) -> actix_web::Result<impl Responder> {
+    let mut queue = scheduler.lock().await.queue().await;
+
+    // only show the packages the secret may read
+    let allowed = |p: &String| auth.allows_package(p);
+    queue.waiting.retain(allowed);
+    queue.locked.retain(allowed);
+    queue.scheduled.iter_mut().for_each(|t| t.packages.retain(allowed));
+    queue.scheduled.retain(|t| !t.packages.is_empty());
+
+    for session in &mut queue.sessions {
+        session.building.retain(allowed);
+        session.pending.retain(|(p, _)| allowed(p));
+    }
+    queue.sessions.retain(|s| !s.building.is_empty() || !s.pending.is_empty());
+
+    Ok(Json(queue))
+}
+
+#[post("/purge")]
+pub async fn purge(
+    auth: AuthWrite<AdminScope>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    auth.get_authorized().check_unrestricted()?;
+
+    let info = database::retention::purge(&db).await.internal()?;
+    auth.audit(&db).await;
+
+    Ok(Json(info))
+}
+
+#[get("/secrets")]
+pub async fn list_secrets(
+    auth: AuthWrite<AdminScope>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    auth.get_authorized().check_unrestricted()?;
+
+    let secrets = AuthorizedSecret::find_all(&db).await.internal()?;
+    Ok(Json(secrets.iter().map(|s| s.to_info()).collect::<Vec<_>>()))
+}
+
+#[post("/secrets")]
+pub async fn add_secret(
+    auth: AuthWrite<AdminScope>,
+    body: Json<SecretAddRequest>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    auth.get_authorized().check_unrestricted()?;
+
+    let body = body.into_inner();
+
+    if !is_hash(&body.hash) {
+        return Err(ErrorBadRequest(
+            "secret is not hashed, use the line printed by `serene host secret`",
+        ));
+    }
+
+    if body.scopes.is_empty() {
+        return Err(ErrorBadRequest("secret must be granted at least one scope"));
+    }
+
+    if AuthorizedSecret::find(&body.hash, &db).await.internal()?.is_some() {
+        return Err(ErrorBadRequest("secret is already authorized"));
+    }
+
+    AuthorizedSecret::new(
+        body.hash.clone(),
+        body.comment.clone(),
+        body.scopes.clone(),
+        body.packages.clone(),
+    )
+    .save(&db)
+    .await
+    .internal()?;
+    auth.audit_with(&db, &body).await;
+
+    let secret = AuthorizedSecret::find(&body.hash, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorInternalServerError("secret was not saved"))?;
+
+    Ok(Json(secret.to_info()))
+}
+
+#[delete("/secrets/{id}")]
+pub async fn revoke_secret(
+    auth: AuthWrite<AdminScope>,
+    id: Path<i64>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    auth.get_authorized().check_unrestricted()?;
+
+    let secret = AuthorizedSecret::find_by_id(*id, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("no authorized secret with id {id}")))?;
+
+    if secret.hash == auth.get_authorized().hash {
+        return Err(ErrorBadRequest("the secret used for this request cannot be revoked"));
+    }
+
+    secret.revoke(&db).await.internal()?;
+    auth.audit(&db).await;
+
     Ok(empty_response())
 }
 
+#[get("/audit")]
+pub async fn audit(
+    auth: AuthWrite<AdminScope>,
+    Query(count): Query<CountQuery>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    auth.get_authorized().check_unrestricted()?;
+
+    let entries = AuditEntry::find_latest_n(count.count.unwrap_or(DEFAULT_AUDIT_COUNT), &db)
+        .await
+        .internal()?;
+
+    Ok(Json(entries.iter().map(|e| e.to_info()).collect::<Vec<_>>()))
+}
+
 async fn get_build_for(
     base: &str,
     time: &str,
@@ -288,6 +534,7 @@ pub async fn get_logs(
     _: AuthRead,
     path: Path<(String, String)>,
     db: Data<Database>,
+    req: HttpRequest,
 ) -> actix_web::Result<impl Responder> {
     let (package, time) = path.into_inner();
 
@@ -295,12 +542,81 @@ pub async fn get_logs(
         .await?
         .ok_or_else(|| ErrorNotFound("package not found or no build at this time"))?;
 
-    Ok(Json(
-        database::log::read(&b)
+    // clients have to ask for plain text, the logs are a json string otherwise
+    let plain = req
+        .headers()
+        .get(ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/plain"));
+
+    if !plain {
+        let logs = database::log::read(&b)
             .await
             .internal()?
-            .ok_or_else(|| ErrorNotFound("build does not have any logs"))?,
-    ))
+            .ok_or_else(|| ErrorNotFound("build does not have any logs"))?;
+
+        return Ok(HttpResponse::Ok().json(logs));
+    }
+
+    let logs = database::log::open(&b)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound("build does not have any logs"))?;
+
+    // logs are decompressed while sending them
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .streaming(ReaderStream::new(logs)))
+}
+
+#[get("/package/{name}/build/{time}/sections")]
+pub async fn get_log_sections(
+    _: AuthRead,
+    path: Path<(String, String)>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    let (package, time) = path.into_inner();
+
+    let b = get_build_for(&package, &time, &db)
+        .await?
+        .ok_or_else(|| ErrorNotFound("package not found or no build at this time"))?;
+
+    let logs = database::log::read(&b)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound("build does not have any logs"))?;
+
+    Ok(Json(database::log::sections(&b, &logs).into_iter().map(|(s, _)| s).collect::<Vec<_>>()))
+}
+
+#[get("/package/{name}/build/{time}/sections/{section}")]
+pub async fn get_log_section(
+    _: AuthRead,
+    path: Path<(String, String, String)>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    let (package, time, section) = path.into_inner();
+
+    let b = get_build_for(&package, &time, &db)
+        .await?
+        .ok_or_else(|| ErrorNotFound("package not found or no build at this time"))?;
+
+    let logs = database::log::read(&b)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound("build does not have any logs"))?;
+
+    let sections = database::log::sections(&b, &logs);
+    let names = sections.iter().map(|(s, _)| s.name.clone()).collect::<Vec<_>>();
+
+    let (_, content) = sections.into_iter().find(|(s, _)| s.name == section).ok_or_else(|| {
+        ErrorNotFound(format!(
+            "build logs have no section '{section}', available are: {}",
+            names.join(", ")
+        ))
+    })?;
+
+    Ok(HttpResponse::Ok().content_type("text/plain; charset=utf-8").body(content))
 }
 
 #[get("/package/{name}/build/logs/subscribe")]
@@ -319,12 +635,47 @@ pub async fn subscribe_logs(
     broadcast.subscribe(package).await
 }
 
+#[derive(Deserialize)]
+struct EventsQuery {
+    /// comma separated list of packages to receive events for
+    packages: Option<String>,
+    /// comma separated list of events to receive
+    events: Option<String>,
+    /// also receive build logs
+    #[serde(default)]
+    logs: bool,
+}
+
+#[get("/events")]
+pub async fn subscribe_events(
+    auth: AuthRead,
+    Query(query): Query<EventsQuery>,
+    broadcast: Data<Broadcast>,
+) -> actix_web::Result<impl Responder> {
+    let split = |list: String| {
+        list.split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect::<HashSet<_>>()
+    };
+
+    broadcast
+        .subscribe_all(EventFilter {
+            packages: query.packages.map(split),
+            events: query.events.map(split),
+            logs: query.logs,
+            secret: auth.get_authorized().clone(),
+        })
+        .await
+}
+
 #[delete("/package/{name}")]
 pub async fn remove(
-    _: AuthWrite,
+    auth: AuthWrite<ManageScope>,
     package: Path<String>,
     db: Data<Database>,
     builder: BuilderData,
+    broadcast: Data<Broadcast>,
 ) -> actix_web::Result<impl Responder> {
     let package = Package::find(&package, &db)
         .await
@@ -332,26 +683,19 @@ pub async fn remove(
         .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
 
     builder.run_remove(&package).await.internal()?;
+    broadcast.notify(&package.base, BroadcastEvent::Remove).await;
+    auth.audit(&db).await;
 
     Ok(empty_response())
 }
 
-#[post("/package/{name}/set")]
-pub async fn settings(
-    _: AuthWrite,
-    package: Path<String>,
-    body: Json<PackageSettingsRequest>,
-    db: Data<Database>,
-    scheduler: BuildSchedulerData,
-    srcinfo_generator: SrcinfoGeneratorData,
-) -> actix_web::Result<impl Responder> {
-    let mut package = Package::find(&package, &db)
-        .await
-        .internal()?
-        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
-
-    // get repo and devel tag
-    let (reschedule, source) = match body.0 {
+/// changes a setting of a package, returns whether the package has to be
+/// rescheduled and whether its source was changed
+fn change_setting(
+    package: &mut Package,
+    setting: PackageSettingsRequest,
+) -> actix_web::Result<(bool, bool)> {
+    let changed = match setting {
         PackageSettingsRequest::Clean(b) => {
             package.clean = b;
             (false, false)
@@ -393,8 +737,54 @@ pub async fn settings(
             package.source.srcinfo_override = b;
             (false, true)
         }
+        PackageSettingsRequest::Timeout(t) => {
+            package.timeout = t;
+            (false, false)
+        }
+        PackageSettingsRequest::Limits(l) => {
+            package.limits = l;
+            (false, false)
+        }
+        PackageSettingsRequest::Notify(n) => {
+            package.notify = n;
+            (false, false)
+        }
+        PackageSettingsRequest::Review(b) => {
+            package.review = b;
+            (false, false)
+        }
     };
 
+    Ok(changed)
+}
+
+#[post("/package/{name}/set")]
+pub async fn settings(
+    auth: AuthWrite<ManageScope>,
+    package: Path<String>,
+    body: Json<PackageSettingsRequest>,
+    db: Data<Database>,
+    scheduler: BuildSchedulerData,
+    srcinfo_generator: SrcinfoGeneratorData,
+    broadcast: Data<Broadcast>,
+) -> actix_web::Result<impl Responder> {
+    let mut package = Package::find(&package, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
+
+    // prepare commands of private packages are not shown to users, so they
+    // shouldn't end up in the audit log either
+    let redact = package.private && matches!(body.0, PackageSettingsRequest::Prepare(Some(_)));
+    let audited = if redact {
+        serde_json::to_value(PackageSettingsRequest::Prepare(Some(REDACTED.to_string())))
+    } else {
+        serde_json::to_value(&body.0)
+    }
+    .unwrap_or_default();
+
+    let (reschedule, source) = change_setting(&mut package, body.0)?;
+
     if reschedule {
         if package.enabled {
             scheduler.lock().await.schedule(&package).await.internal()?;
@@ -412,6 +802,35 @@ pub async fn settings(
         package.change_settings(&db).await.internal()?;
     }
 
+    broadcast.notify(&package.base, BroadcastEvent::Settings).await;
+    auth.audit_with(&db, &audited).await;
+
+    Ok(empty_response())
+}
+
+#[post("/package/{name}/trust")]
+pub async fn trust(
+    auth: AuthWrite<ManageScope>,
+    package: Path<String>,
+    db: Data<Database>,
+    broadcast: Data<Broadcast>,
+) -> actix_web::Result<impl Responder> {
+    let mut package = Package::find(&package, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
+
+    if package.source.get_maintainer_change().is_none() {
+        return Err(ErrorBadRequest("maintainer of the package did not change"));
+    }
+
+    // the package stays disabled, so the user can look at it before building again
+    package.source.acknowledge_maintainer();
+    package.change_sources(&db).await.internal()?;
+
+    broadcast.notify(&package.base, BroadcastEvent::Settings).await;
+    auth.audit(&db).await;
+
     Ok(empty_response())
 }
 
@@ -429,16 +848,40 @@ pub async fn get_signature_public_key(_: AuthRead) -> actix_web::Result<impl Res
 
 #[get("/webhook/package/{name}/secret")]
 pub async fn get_webhook_secret(
-    auth: AuthWrite,
+    auth: AuthWrite<BuildScope>,
     db: Data<Database>,
     package: Path<String>,
 ) -> actix_web::Result<impl Responder> {
-    let _ = Package::find(&package, &db)
+    let package = Package::find(&package, &db)
         .await
         .internal()?
         .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
 
-    create_webhook_secret(&package, &serene_data::secret::hash(auth.get_secret())).map(Json)
+    let secret = create_webhook_secret(&package, &serene_data::secret::hash(auth.get_secret()))?;
+    auth.audit(&db).await;
+
+    Ok(Json(secret))
+}
+
+#[post("/webhook/package/{name}/rotate")]
+pub async fn rotate_webhook_secret(
+    auth: AuthWrite<BuildScope>,
+    db: Data<Database>,
+    package: Path<String>,
+) -> actix_web::Result<impl Responder> {
+    let mut package = Package::find(&package, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
+
+    // invalidates the webhook secrets of all users for this package
+    package.webhook_nonce = Some(generate_webhook_nonce());
+    package.change_webhook(&db).await.internal()?;
+
+    let secret = create_webhook_secret(&package, &serene_data::secret::hash(auth.get_secret()))?;
+    auth.audit(&db).await;
+
+    Ok(Json(secret))
 }
 
 #[post("/webhook/package/{name}/build")]
@@ -453,6 +896,8 @@ pub async fn build_webhook(
         .internal()?
         .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
 
+    package.mark_webhook_fired(&db).await.internal()?;
+
     scheduler
         .lock()
         .await
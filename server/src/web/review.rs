@@ -0,0 +1,73 @@
+use crate::build::schedule::BuildMeta;
+use crate::database::Database;
+use crate::package::Package;
+use crate::web::auth::{AuthRead, AuthWrite, ManageScope};
+use crate::web::broadcast::Broadcast;
+use crate::web::{BuildSchedulerData, InternalError, empty_response};
+use actix_web::error::ErrorNotFound;
+use actix_web::web::{Data, Json, Path};
+use actix_web::{Responder, get, post};
+use serene_data::build::BuildReason;
+use serene_data::package::{BroadcastEvent, PackageReview, PackageReviewRequest};
+
+#[get("/package/{name}/review")]
+pub async fn get_review(
+    _: AuthRead,
+    package: Path<String>,
+    db: Data<Database>,
+) -> actix_web::Result<impl Responder> {
+    let package = Package::find(&package, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
+
+    let pending = package
+        .review_pending
+        .ok_or_else(|| ErrorNotFound("no changes of the package are awaiting review"))?;
+
+    Ok(Json(PackageReview { created: pending.created, diff: pending.diff }))
+}
+
+#[post("/package/{name}/review")]
+pub async fn review(
+    auth: AuthWrite<ManageScope>,
+    package: Path<String>,
+    body: Json<PackageReviewRequest>,
+    db: Data<Database>,
+    scheduler: BuildSchedulerData,
+    broadcast: Data<Broadcast>,
+) -> actix_web::Result<impl Responder> {
+    auth.get_authorized().check_package(&package)?;
+
+    let mut package = Package::find(&package, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
+
+    let pending = package
+        .review_pending
+        .take()
+        .ok_or_else(|| ErrorNotFound("no changes of the package are awaiting review"))?;
+
+    if body.approve {
+        package.review_approved = Some(pending.fingerprint);
+        package.review_rejected = None;
+    } else {
+        package.review_rejected = Some(pending.fingerprint);
+    }
+
+    package.change_review(&db).await.internal()?;
+    broadcast.notify(&package.base, BroadcastEvent::Settings).await;
+    auth.audit_with(&db, &body.0).await;
+
+    if body.approve {
+        scheduler
+            .lock()
+            .await
+            .run(vec![package], BuildMeta::normal(BuildReason::Manual))
+            .await
+            .internal()?;
+    }
+
+    Ok(empty_response())
+}
@@ -0,0 +1,406 @@
+use crate::build::BuildSummary;
+use crate::config::INFO;
+use crate::database::{self, Database};
+use crate::package::Package;
+use crate::web::InternalError;
+use crate::web::auth::{AuthRead, SECRET_COOKIE, secret_authorized};
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::error::ErrorNotFound;
+use actix_web::http::header::LOCATION;
+use actix_web::web::{Data, Form, Path};
+use actix_web::{HttpRequest, HttpResponse, get, post};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde::Deserialize;
+use serene_data::build::BuildState;
+use serene_data::secret::Scope;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; max-width: 70rem; margin: 2rem auto; padding: 0 1rem; color: #222; }
+a { color: #1565c0; text-decoration: none; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5rem; }
+th, td { text-align: left; padding: 0.3rem 0.6rem; border-bottom: 1px solid #ddd; }
+th { font-weight: 600; }
+pre { background: #f5f5f5; padding: 1rem; overflow-x: auto; font-size: 0.85rem; }
+header { display: flex; justify-content: space-between; align-items: baseline; }
+.dim { color: #888; }
+.success { color: #2e7d32; } .failure, .fatal { color: #c62828; }
+.running, .pending { color: #1565c0; } .cancelled, .stopped, .review { color: #ef6c00; }
+"#;
+
+/// escapes text so it can be embedded into html
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// percent-encodes text so it can be used as a segment of an url path
+fn encode_segment(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// renders a full page with the given title and body
+fn page(title: &str, body: &str) -> HttpResponse {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width"><title>{title} - serene</title><style>{STYLE}</style></head>
+<body>
+<header><h1><a href="/ui">serene</a></h1><span class="dim">version {version} - <a href="/ui/logout">logout</a></span></header>
+{body}
+</body>
+</html>"#,
+        title = escape(title),
+        version = escape(&INFO.version),
+    ))
+}
+
+/// redirects the browser to a different page
+fn redirect(location: &str) -> HttpResponse {
+    HttpResponse::SeeOther().insert_header((LOCATION, location)).finish()
+}
+
+/// returns the name and css class of a build state
+fn state(state: &BuildState) -> String {
+    let (name, detail) = match state {
+        BuildState::Pending => ("pending", None),
+        BuildState::Cancelled(m) => ("cancelled", Some(m.clone())),
+        BuildState::Running(p) => ("running", Some(format!("on {p}"))),
+        BuildState::Success => ("success", None),
+        BuildState::Failure => ("failure", None),
+        BuildState::Fatal(m, p) => ("fatal", Some(format!("on {p}: {m}"))),
+        BuildState::Stopped(p) => ("stopped", Some(format!("on {p}"))),
+        BuildState::Review => ("review", Some("awaiting review of changes".to_string())),
+    };
+
+    let title = detail.map(|d| format!(r#" title="{}""#, escape(&d))).unwrap_or_default();
+    format!(r#"<span class="{name}"{title}>{name}</span>"#)
+}
+
+fn time(time: &DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn duration(build: &BuildSummary) -> String {
+    build
+        .ended
+        .map(|e| {
+            let seconds = (e - build.started).num_seconds();
+            format!("{}m {:02}s", seconds / 60, seconds % 60)
+        })
+        .unwrap_or_else(|| "running".to_string())
+}
+
+fn bytes(bytes: usize) -> String {
+    const SUFFIXES: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut index = 0;
+    while value > 1024.0 && index < SUFFIXES.len() - 1 {
+        value /= 1024.0;
+        index += 1;
+    }
+
+    format!("{value:.2} {}", SUFFIXES[index])
+}
+
+/// returns the id used to link to a build
+fn build_id(build: &BuildSummary) -> String {
+    build.started.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+#[get("/ui")]
+pub async fn ui_list(
+    auth: Option<AuthRead>,
+    db: Data<Database>,
+) -> actix_web::Result<HttpResponse> {
+    let Some(auth) = auth else {
+        return Ok(redirect("/ui/login"));
+    };
+
+    let mut rows = String::new();
+    for package in Package::find_all(&db).await.internal()? {
+        if !auth.allows_package(&package.base) {
+            continue;
+        }
+
+        let build = BuildSummary::find_latest_for_package(&package.base, &db).await.internal()?;
+
+        rows += &format!(
+            r#"<tr><td><a href="/ui/package/{base}">{base}</a></td><td>{version}</td><td>{state}</td><td>{built}</td><td class="dim">{tags}</td></tr>"#,
+            base = escape(&package.base),
+            version = escape(&package.get_version().unwrap_or_else(|| "unknown".to_string())),
+            state = build
+                .as_ref()
+                .map(|b| state(&b.state))
+                .unwrap_or_else(|| "never built".to_string()),
+            built = build.as_ref().map(|b| time(&b.started)).unwrap_or_default(),
+            tags = [
+                if package.enabled { "" } else { "disabled" },
+                if package.source.devel { "devel" } else { "" },
+                if package.dependency { "dependency" } else { "" },
+            ]
+            .into_iter()
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+        );
+    }
+
+    Ok(page(
+        "packages",
+        &format!(
+            "<h2>packages</h2><table><tr><th>name</th><th>version</th><th>latest build</th><th>built</th><th></th></tr>{rows}</table>"
+        ),
+    ))
+}
+
+#[get("/ui/package/{name}")]
+pub async fn ui_package(
+    auth: Option<AuthRead>,
+    name: Path<String>,
+    db: Data<Database>,
+) -> actix_web::Result<HttpResponse> {
+    if auth.is_none() {
+        return Ok(redirect("/ui/login"));
+    }
+
+    let package = Package::find(&name, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &name)))?;
+
+    let info = package.to_info(0);
+    let builds = BuildSummary::find_all_for_package(&package.base, &db).await.internal()?;
+
+    let mut body = format!("<h2>{}</h2>", escape(&package.base));
+    if let Some(description) = &info.description {
+        body += &format!("<p>{}</p>", escape(description));
+    }
+    if let Some(url) = &info.upstream_url {
+        // the url comes from the pkgbuild, so only link to web pages
+        if url.starts_with("http://") || url.starts_with("https://") {
+            body += &format!(r#"<p><a href="{url}">{url}</a></p>"#, url = escape(url));
+        } else {
+            body += &format!("<p>{}</p>", escape(url));
+        }
+    }
+
+    let limits = [
+        info.limits.memory.map(|m| format!("{m} MiB")),
+        info.limits.cpus.map(|c| format!("{c} cpus")),
+        info.limits.pids.map(|p| format!("{p} processes")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    let properties = [
+        ("members", info.members.join(" ")),
+        ("version", info.version.clone().unwrap_or_else(|| "unknown".to_string())),
+        ("source", format!("{} {}", info.source, info.source_url.clone().unwrap_or_default())),
+        ("added", time(&info.added)),
+        ("enabled", info.enabled.to_string()),
+        ("clean", info.clean.to_string()),
+        ("schedule", info.schedule.clone()),
+        (
+            "timeout",
+            info.timeout.map(|t| format!("{t} minutes")).unwrap_or_else(|| "none".to_string()),
+        ),
+        ("limits", if limits.is_empty() { "none".to_string() } else { limits.join(", ") }),
+        (
+            "flags",
+            info.makepkg_flags.iter().map(|f| format!("--{f}")).collect::<Vec<_>>().join(" "),
+        ),
+    ];
+
+    body += "<table>";
+    for (name, value) in properties {
+        body += &format!("<tr><th>{name}</th><td>{}</td></tr>", escape(&value));
+    }
+    body += "</table>";
+
+    body += "<h3>builds</h3><table><tr><th>id</th><th>version</th><th>state</th><th>reason</th><th>started</th><th>duration</th><th>peak memory</th><th>cpu time</th><th>io</th></tr>";
+    for (i, build) in builds.iter().enumerate() {
+        let stats = build.stats.as_ref();
+
+        body += &format!(
+            r#"<tr><td><a href="/ui/package/{base}/build/{id}">{index:0>4}</a></td><td>{version}</td><td>{state}</td><td>{reason}</td><td>{started}</td><td>{duration}</td><td>{memory}</td><td>{cpu}</td><td>{io}</td></tr>"#,
+            base = escape(&package.base),
+            id = build_id(build),
+            index = builds.len() - i - 1,
+            version = escape(build.version.as_deref().unwrap_or("unknown")),
+            state = state(&build.state),
+            reason = build.reason,
+            started = time(&build.started),
+            duration = duration(build),
+            memory = stats.and_then(|s| s.mem_peak).map(bytes).unwrap_or_default(),
+            cpu = stats
+                .and_then(|s| s.cpu_user.zip(s.cpu_system))
+                .map(|(u, s)| format!("{:.1}s", (u + s) as f64 / 1_000_000.0))
+                .unwrap_or_default(),
+            io = stats
+                .and_then(|s| s.io_tbr.zip(s.io_tbw))
+                .map(|(r, w)| format!("{} / {}", bytes(r), bytes(w)))
+                .unwrap_or_default(),
+        );
+    }
+    body += "</table>";
+
+    if let Some(pkgbuild) = &package.pkgbuild {
+        body += &format!(
+            "<details><summary>PKGBUILD</summary><pre>{}</pre></details>",
+            escape(pkgbuild)
+        );
+    }
+
+    Ok(page(&package.base, &body))
+}
+
+#[get("/ui/package/{name}/build/{time}")]
+pub async fn ui_build(
+    auth: Option<AuthRead>,
+    path: Path<(String, String)>,
+    db: Data<Database>,
+) -> actix_web::Result<HttpResponse> {
+    if auth.is_none() {
+        return Ok(redirect("/ui/login"));
+    }
+
+    let (name, id) = path.into_inner();
+    let started = DateTime::parse_from_rfc3339(&id)
+        .map_err(|_| ErrorNotFound("no valid build time given"))?
+        .to_utc();
+
+    let build = BuildSummary::find(&started, &name, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound("package not found or no build at this time"))?;
+
+    let mut body = format!(
+        r#"<h2><a href="/ui/package/{base}">{base}</a> build</h2><table>"#,
+        base = escape(&name)
+    );
+
+    let mut properties = vec![
+        ("state", state(&build.state)),
+        ("reason", build.reason.to_string()),
+        ("version", escape(build.version.as_deref().unwrap_or("unknown"))),
+        ("started", time(&build.started)),
+        ("duration", duration(&build)),
+    ];
+
+    if let Some(failure) = &build.failure {
+        properties.push(("failure", failure.category.to_string()));
+        properties.push(("hint", escape(&failure.hint)));
+    }
+
+    if let Some(stats) = &build.stats {
+        if let Some(mem) = stats.mem_peak {
+            properties.push(("peak memory", bytes(mem)));
+        }
+        if let Some(kills) = stats.oom_kills.filter(|k| *k > 0) {
+            properties.push(("oom kills", kills.to_string()));
+        }
+        if let Some(user) = stats.cpu_user {
+            properties.push(("cpu user", format!("{:.1}s", user as f64 / 1_000_000.0)));
+        }
+        if let Some(system) = stats.cpu_system {
+            properties.push(("cpu system", format!("{:.1}s", system as f64 / 1_000_000.0)));
+        }
+        if let Some(read) = stats.io_tbr {
+            properties.push(("io read", bytes(read)));
+        }
+        if let Some(written) = stats.io_tbw {
+            properties.push(("io written", bytes(written)));
+        }
+    }
+
+    for (name, value) in properties {
+        body += &format!("<tr><th>{name}</th><td>{value}</td></tr>");
+    }
+    body += "</table><h3>logs</h3>";
+
+    if build.state.done() {
+        let logs = database::log::read(&build).await.internal()?;
+
+        body += &format!(
+            "<pre>{}</pre>",
+            logs.map(|l| escape(&l)).unwrap_or_else(|| "this build has no logs".to_string())
+        );
+    } else {
+        // the broadcast first sends the logs so far and then the live ones
+        body += &format!(
+            r#"<pre id="logs" data-source="{source}"></pre>
+<script>
+const logs = document.getElementById("logs");
+const source = new EventSource(logs.dataset.source);
+source.addEventListener("log", e => {{ logs.textContent += JSON.parse(e.data); }});
+source.addEventListener("change", e => {{
+    const state = JSON.parse(e.data).state;
+    if (!["pending", "running"].includes(state)) {{ source.close(); location.reload(); }}
+}});
+</script>"#,
+            source = escape(&format!("/package/{}/build/logs/subscribe", encode_segment(&name)))
+        );
+    }
+
+    Ok(page(&format!("{name} build"), &body))
+}
+
+#[get("/ui/login")]
+pub async fn ui_login_form() -> HttpResponse {
+    page(
+        "login",
+        r#"<h2>login</h2>
+<form method="post" action="/ui/login">
+<p>Enter a secret which is authorized on this server.</p>
+<input type="password" name="secret" autofocus> <button type="submit">login</button>
+</form>"#,
+    )
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    secret: String,
+}
+
+#[post("/ui/login")]
+pub async fn ui_login(
+    req: HttpRequest,
+    form: Form<LoginForm>,
+    db: Data<Database>,
+) -> actix_web::Result<HttpResponse> {
+    if !secret_authorized(&db, &form.secret, Scope::Read).await? {
+        return Ok(page(
+            "login",
+            r#"<h2>login</h2><p class="failure">The secret is not authorized.</p><a href="/ui/login">try again</a>"#,
+        ));
+    }
+
+    let cookie = Cookie::build(SECRET_COOKIE, form.into_inner().secret)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .secure(req.connection_info().scheme() == "https")
+        .finish();
+
+    Ok(HttpResponse::SeeOther().insert_header((LOCATION, "/ui")).cookie(cookie).finish())
+}
+
+#[get("/ui/logout")]
+pub async fn ui_logout() -> HttpResponse {
+    let mut cookie = Cookie::build(SECRET_COOKIE, "").path("/").finish();
+    cookie.make_removal();
+
+    HttpResponse::SeeOther().insert_header((LOCATION, "/ui/login")).cookie(cookie).finish()
+}
@@ -0,0 +1,120 @@
+use crate::build::schedule::BuildMeta;
+use crate::database::Database;
+use crate::package::Package;
+use crate::web::auth::webhook_secrets;
+use crate::web::{BuildSchedulerData, InternalError};
+use actix_web::error::{ErrorForbidden, ErrorNotFound, ErrorPayloadTooLarge, ErrorUnauthorized};
+use actix_web::web::{Data, Path, Payload};
+use actix_web::{HttpRequest, HttpResponse, post};
+use hmac::{Hmac, Mac};
+use log::debug;
+use serde::Deserialize;
+use serene_data::secret;
+use sha2::Sha256;
+
+/// header github signs the payload in, prefixed with `sha256=`
+const GITHUB_SIGNATURE: &str = "X-Hub-Signature-256";
+/// header gitea and forgejo sign the payload in
+const GITEA_SIGNATURE: &str = "X-Gitea-Signature";
+/// header gitlab sends the plain secret in
+const GITLAB_TOKEN: &str = "X-Gitlab-Token";
+/// maximum size of a webhook payload, github caps them at 25 MB and pushes
+/// with many commits easily exceed the default limit
+const PAYLOAD_LIMIT: usize = 25 * 1024 * 1024;
+
+/// payload of a push event, the used fields are the same on all forges
+#[derive(Deserialize)]
+struct PushEvent {
+    /// ref which was pushed to, e.g. `refs/heads/main`
+    #[serde(rename = "ref")]
+    reference: String,
+    /// commit the ref points to after the push
+    after: String,
+}
+
+/// verifies a hex encoded hmac-sha256 signature of the body
+fn verify_hmac(body: &[u8], secret: &str, signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// checks whether the request was signed by a forge with the given secret,
+/// returns none if the request carries no signature at all
+fn verify(req: &HttpRequest, body: &[u8], secret: &str) -> Option<bool> {
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(signature) = header(GITHUB_SIGNATURE) {
+        Some(signature.strip_prefix("sha256=").is_some_and(|s| verify_hmac(body, secret, s)))
+    } else if let Some(signature) = header(GITEA_SIGNATURE) {
+        Some(verify_hmac(body, secret, signature))
+    } else {
+        // hashes are compared so the time taken does not leak the secret
+        header(GITLAB_TOKEN).map(|token| secret::hash(token) == secret::hash(secret))
+    }
+}
+
+#[post("/webhook/package/{name}/push")]
+pub async fn push_webhook(
+    req: HttpRequest,
+    package: Path<String>,
+    payload: Payload,
+    db: Data<Database>,
+    scheduler: BuildSchedulerData,
+) -> actix_web::Result<HttpResponse> {
+    let body = payload.to_bytes_limited(PAYLOAD_LIMIT).await.map_err(ErrorPayloadTooLarge)??;
+    let mut signed = false;
+    for webhook_secret in webhook_secrets(&db, &package).await? {
+        match verify(&req, &body, &webhook_secret) {
+            None => return Err(ErrorUnauthorized("no webhook signature provided")),
+            Some(valid) => signed |= valid,
+        }
+    }
+
+    if !signed {
+        return Err(ErrorForbidden("invalid webhook signature"));
+    }
+
+    let package = Package::find(&package, &db)
+        .await
+        .internal()?
+        .ok_or_else(|| ErrorNotFound(format!("package with base {} is not added", &package)))?;
+
+    package.mark_webhook_fired(&db).await.internal()?;
+
+    // forges also send other events, like a ping when the webhook is created
+    let Ok(event) = serde_json::from_slice::<PushEvent>(&body) else {
+        return Ok(HttpResponse::Ok().body("not a push event, ignoring"));
+    };
+
+    let branch = event.reference.strip_prefix("refs/heads/");
+    if let Some(tracked) = package.source.get_branch(&package.get_folder()).await.internal()?
+        && branch != Some(tracked.as_str())
+    {
+        debug!("ignoring push to {} for {}, tracking {tracked}", event.reference, package.base);
+        return Ok(HttpResponse::Ok()
+            .body(format!("push to {} ignored, package tracks {tracked}", event.reference)));
+    }
+
+    // deleting a ref is a push to the zero commit
+    if event.after.chars().all(|c| c == '0') {
+        return Ok(HttpResponse::Ok().body(format!("{} was deleted, ignoring", event.reference)));
+    }
+
+    let base = package.base.clone();
+    scheduler
+        .lock()
+        .await
+        .run(vec![package], BuildMeta::pushed(event.after.clone()))
+        .await
+        .internal()?;
+
+    Ok(HttpResponse::Ok().body(format!("building {base} for commit {}", event.after)))
+}